@@ -1,29 +1,110 @@
 use serde_derive::{Deserialize, Serialize};
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
-use csv::{QuoteStyle, Writer, WriterBuilder};
+use csv::{QuoteStyle, WriterBuilder};
+use base64::Engine;
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
-use regex::Regex;
+use log::info;
+use crate::cdx_licensing;
+use crate::cdx_proto;
+use crate::cdx_xml;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct License{
+    #[serde(rename = "bom-ref")]
+    pub bom_ref: Option<String>,
     pub id: Option<String>,
     pub name: Option<String>,
+    pub text: Option<AttachedText>,
     pub url: Option<String>,    
+    pub acknowledgement: Option<String>,
+    pub licensing: Option<Licensing>,
+}
+
+/// Commercial licensing details of a license (CycloneDX 1.5+).
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Licensing{
+    #[serde(rename = "altIds")]
+    pub alt_ids: Option<Vec<String>>,
+    pub licensor: Option<OrganizationalEntityOrContact>,
+    pub licensee: Option<OrganizationalEntityOrContact>,
+    pub purchaser: Option<OrganizationalEntityOrContact>,
+    #[serde(rename = "purchaseOrder")]
+    pub purchase_order: Option<String>,
+    #[serde(rename = "licenseTypes")]
+    pub license_types: Option<Vec<String>>,
+    #[serde(rename = "lastRenewal")]
+    pub last_renewal: Option<String>,
+    pub expiration: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrganizationalEntityOrContact{
+    pub organization: Option<OrganizationalEntity>,
+    pub individual: Option<OrganizationalContact>,
+}
+
+impl OrganizationalEntityOrContact{
+    pub fn name(&self) -> String{
+        self.organization.as_ref().and_then(|o| o.name.clone())
+            .or(self.individual.as_ref().and_then(|i| i.name.clone()))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct AttachedText{
+    pub content: String,
+    #[serde(rename = "contentType")]
+    pub content_type: Option<String>,
+    pub encoding: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct LicenseEntry{
     pub license: Option<License>,
     pub expression: Option<String>,
+    // CycloneDX 1.6 sets `acknowledgement` on expressions directly and on licenses inside `license`.
+    pub acknowledgement: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Component{
+    #[serde(rename = "bom-ref")]
+    pub bom_ref: Option<String>,
+    #[serde(rename = "type")]
+    pub component_type: Option<String>,
+    pub supplier: Option<OrganizationalEntity>,
+    pub author: Option<String>,
+    // CycloneDX 1.6 replaces `author` with a list of contacts.
+    pub authors: Option<Vec<OrganizationalContact>>,
+    pub publisher: Option<String>,
+    pub group: Option<String>,
     pub name: String,
+    pub version: Option<String>,
+    pub scope: Option<String>,
     pub licenses: Option<Option<Vec<LicenseEntry>>>,
     pub purl: Option<String>,
     pub cpe: Option<Option<String>>,
+    pub swid: Option<Swid>,
+    pub components: Option<Vec<Component>>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrganizationalEntity{
+    pub name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrganizationalContact{
+    pub name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Swid{
+    #[serde(rename = "tagId")]
+    pub tag_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -40,32 +121,265 @@ pub struct SBOMMetadata{
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SBOMComponent{
+    #[serde(rename = "bom-ref")]
+    pub bom_ref: Option<String>,
     pub group: Option<Option<String>>,
     pub version: Option<Option<String>>,
     pub name: String,
+    pub purl: Option<String>,
+    pub cpe: Option<Option<String>>,
+    pub swid: Option<Swid>,
     pub licenses: Option<Option<Vec<LicenseEntry>>>,
 }
 
 #[derive(Serialize, Debug)]
 pub struct LicenseHeader<'a>{
-    name: &'a String,
+    #[serde(rename = "product name")]
+    product_name: &'a String,
     namespace: &'a String,
+    #[serde(rename = "product group")]
+    product_group: &'a String,
+    #[serde(rename = "product version")]
+    product_version: &'a String,
+    #[serde(rename = "package name")]
+    package_name: &'a String,
     group: &'a String,
     version: &'a String,
+    #[serde(rename = "type")]
+    component_type: &'a String,
+    scope: &'a String,
+    publisher: &'a String,
+    author: &'a String,
+    supplier: &'a String,
     #[serde(rename = "package reference")]
     package_reference: &'a String,
+    #[serde(rename = "package identifier")]
+    package_identifier: &'a String,
+    #[serde(rename = "identifier type")]
+    identifier_type: &'a String,
     license: &'a String,
+    #[serde(rename = "license type")]
+    license_type: &'a String,
     #[serde(rename = "alternate package reference")]
     alternate_reference_locator: &'a String,
+    #[serde(rename = "parent reference")]
+    parent_reference: &'a String,
+    #[serde(rename = "component path")]
+    component_path: &'a String,
+    // `component`, `metadata component` for the product the BOM describes, or
+    // `bom` for `metadata.licenses`, the license of the BOM document itself.
+    #[serde(rename = "license source")]
+    license_source: &'a String,
 }
 
-pub async fn get_cdx_bom_license(filepath: &str, output_path: &String){
+#[derive(Serialize, Deserialize, Debug)]
+pub struct LicenseRefHeader{
+    #[serde(rename = "licenseId")]
+    license_id: String,
+    name: String,
+    #[serde(rename = "extracted text")]
+    extracted_text: String,
+    comment: String,
+}
+
+/// The license value of an entry (expression, id or name) and its `license type`.
+pub fn license_entry_value(entry: &LicenseEntry) -> (String, String){
+    let mut license_value = String::new();
+    if let Some(license) = &entry.license{
+        if let Some(id) = &license.id{
+            license_value = id.clone();
+        } else if let Some(name) = &license.name{
+            license_value = name.clone();
+        }
+    }
+    if let Some(expression) = &entry.expression{
+        license_value = expression.clone();
+    }
+    // `declared` or `concluded`, matching the SPDX license type column.
+    let license_type = entry.acknowledgement.as_ref()
+        .or(entry.license.as_ref().and_then(|l| l.acknowledgement.as_ref()))
+        .cloned()
+        .unwrap_or_default();
+    (license_value, license_type)
+}
+
+/// Gives a license without an SPDX `id` a stable `LicenseRef-` id built from
+/// its name, or its bom-ref when it has no name, e.g.
+/// `LicenseRef-Acme-EULA-018feee2`. Characters outside the SPDX idstring set
+/// are replaced with `-`, so a short SHA-1 of the original value keeps names
+/// such as `Acme EULA` and `Acme/EULA` apart.
+pub fn license_ref_id(license: &License) -> Option<String>{
+    let name = license.name.as_ref().or(license.bom_ref.as_ref()).filter(|n| !n.trim().is_empty())?;
+    let id: String = name.trim().chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect();
+    let hash = sha1_smol::Sha1::from(name.as_bytes()).digest().to_string();
+    Some(format!("LicenseRef-{}-{}", id, &hash[..8]))
+}
+
+// Identifier types in the order they are tried, see `component_identity`.
+const IDENTIFIER_TYPES: [&str; 5] = ["purl", "bom-ref", "cpe", "swid", "name@version"];
+
+/// Identifies a component by the first of: purl, bom-ref, CPE, SWID tagId,
+/// name@version. Returns the identifier and which kind it is.
+pub fn component_identity(component: &Component) -> (String, &'static str){
+    identity(&component.name, component.version.as_deref(), component.purl.as_deref(), component.bom_ref.as_deref(), component.cpe.as_ref(), component.swid.as_ref())
+}
+
+/// [`component_identity`] for the `metadata.component`.
+pub fn sbom_component_identity(component: &SBOMComponent) -> (String, &'static str){
+    identity(&component.name, component.version.clone().flatten().as_deref(), component.purl.as_deref(), component.bom_ref.as_deref(), component.cpe.as_ref(), component.swid.as_ref())
+}
+
+fn identity(name: &str, version: Option<&str>, purl: Option<&str>, bom_ref: Option<&str>, cpe: Option<&Option<String>>, swid: Option<&Swid>) -> (String, &'static str){
+    if let Some(purl) = purl.filter(|p| !p.is_empty()) {
+        return (purl.to_string(), "purl");
+    }
+    if let Some(bom_ref) = bom_ref.filter(|r| !r.is_empty()) {
+        return (bom_ref.to_string(), "bom-ref");
+    }
+    if let Some(cpe) = cpe.and_then(|c| c.as_ref()).filter(|c| !c.is_empty()) {
+        return (cpe.to_string(), "cpe");
+    }
+    if let Some(tag_id) = swid.and_then(|s| s.tag_id.as_ref()).filter(|t| !t.is_empty()) {
+        return (tag_id.to_string(), "swid");
+    }
+    match version.filter(|v| !v.is_empty()) {
+        Some(version) => (format!("{}@{}", name, version), "name@version"),
+        None => (name.to_string(), "name@version"),
+    }
+}
+
+/// The `author` of a component, or its CycloneDX 1.6 `authors` joined.
+fn component_author(component: &Component) -> String{
+    if let Some(author) = &component.author {
+        return author.to_string();
+    }
+    component.authors.iter().flatten()
+        .filter_map(|a| a.name.as_deref())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A component found while walking the tree, with the bom-ref of the
+/// component it is nested in and the names leading to it, e.g. `app > lib`.
+pub struct NestedComponent<'a>{
+    pub component: &'a Component,
+    pub parent_reference: String,
+    pub path: String,
+}
+
+/// Walks `components[].components` depth-first, parents before their children.
+pub fn flatten_components<'a>(components: &'a [Component], parent: Option<&NestedComponent>, nested: &mut Vec<NestedComponent<'a>>){
+    for component in components{
+        let path = match parent {
+            Some(parent) => format!("{} > {}", parent.path, component.name),
+            None => component.name.to_string(),
+        };
+        let parent_reference = parent
+            .map(|p| p.component.bom_ref.as_ref().or(p.component.purl.as_ref()).unwrap_or(&p.component.name).to_string())
+            .unwrap_or_default();
+        nested.push(NestedComponent{ component, parent_reference, path });
+        if let Some(children) = &component.components {
+            let index = nested.len() - 1;
+            let mut children_nested = Vec::new();
+            flatten_components(children, Some(&nested[index]), &mut children_nested);
+            nested.extend(children_nested);
+        }
+    }
+}
+
+/// Parses a CycloneDX BOM in JSON, XML or protobuf form. `format` overrides
+/// detection from the file extension and content.
+pub fn parse_cdx_document(filepath: &str, content: &[u8], format: Option<&str>) -> Result<Components, Box<dyn Error>>{
+    let format = match format {
+        Some(format) => format,
+        None if cdx_proto::is_cdx_protobuf(filepath) => "protobuf",
+        None if cdx_xml::is_cdx_xml(filepath, &String::from_utf8_lossy(content)) => "xml",
+        None => "json",
+    };
+    match format {
+        "protobuf" => cdx_proto::parse_cdx_protobuf(content).map_err(|e| format!("Error converting protobuf: {}", e).into()),
+        "xml" => {
+            let content_str = std::str::from_utf8(content)?;
+            cdx_xml::parse_cdx_xml(content_str).map_err(|e| format!("Error converting XML: {}", e).into())
+        }
+        _ => serde_json::from_slice(content).map_err(|e| format!("Error converting Json: {}", e).into()),
+    }
+}
+
+/// Input format and optional CycloneDX outputs, on top of the license and license ref CSVs.
+#[derive(Debug, Default)]
+pub struct CdxExportOptions{
+    pub format: Option<String>,
+    pub commercial_csv_path: Option<String>,
+    pub expiry_window_days: i64,
+}
+
+pub async fn get_cdx_bom_license(filepath: &str, output_path: &String, ref_file_path: &String, options: &CdxExportOptions){
     let mut file = File::open(filepath).await.expect("Error reading the file, make sure the path exists");
-    let mut content_str = String::new();
-    file.read_to_string(&mut content_str).await.expect("Error Reading file to variable");
-    let data: Components = serde_json::from_str(&content_str).expect("Error converting Json");
-    //let _ = write_cdx_csv(&data, output_path).await;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).await.expect("Error Reading file to variable");
+    let data = parse_cdx_document(filepath, &content, options.format.as_deref()).expect("Error parsing CycloneDX document");
     let _ = write_simple_cdx_csv(&data, output_path).await;
+    let _ = write_cdx_ref_csv(&data, ref_file_path).await;
+    if let Some(commercial_csv_path) = &options.commercial_csv_path {
+        let _ = cdx_licensing::write_commercial_csv(&data, commercial_csv_path, options.expiry_window_days).await;
+    }
+}
+
+/// Decodes an attached license text; base64 attachments that fail to decode
+/// are kept as they are.
+fn attached_text(text: &AttachedText) -> String{
+    if text.encoding.as_deref() != Some("base64") {
+        return text.content.to_string();
+    }
+    let content: String = text.content.split_whitespace().collect();
+    match base64::engine::general_purpose::STANDARD.decode(content) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+        Err(e) => {
+            info!("Could not decode base64 license text: {}", e);
+            text.content.to_string()
+        }
+    }
+}
+
+/// Writes name-only licenses and licenses with embedded text in the layout of
+/// the SPDX license ref CSV. Licenses are keyed by id, or by `bom-ref` when
+/// they have none; the URL goes in the comment column. Each distinct license
+/// is written once.
+pub async fn write_cdx_ref_csv(comp: &Components, ref_file_path: &String) -> Result<(), Box<dyn Error>>{
+    let mut wrt_ref = WriterBuilder::new()
+        .delimiter(b'\t')
+        .quote_style(QuoteStyle::Always)
+        .has_headers(false)
+        .from_path(ref_file_path)?;
+
+    wrt_ref.write_record(["licenseId", "name", "extracted text", "comment"])?;
+
+    let mut nested = Vec::new();
+    flatten_components(&comp.components, None, &mut nested);
+    let product_licenses = comp.metadata.component.iter().flatten().flat_map(|c| c.licenses.iter().flatten().flatten());
+    let bom_licenses = comp.metadata.licenses.iter().flatten().flatten();
+    let component_licenses = nested.iter().flat_map(|n| n.component.licenses.iter().flatten().flatten());
+
+    let mut seen = HashSet::new();
+    for license in product_licenses.chain(bom_licenses).chain(component_licenses).filter_map(|entry| entry.license.as_ref()){
+        if license.id.is_some() && license.text.is_none() {
+            continue;
+        }
+        let row = LicenseRefHeader{
+            license_id: license.id.clone().or_else(|| license_ref_id(license)).unwrap_or_default(),
+            name: license.name.clone().unwrap_or_default(),
+            extracted_text: license.text.as_ref().map(attached_text).unwrap_or_default(),
+            comment: license.url.clone().unwrap_or_default(),
+        };
+        if seen.insert((row.license_id.clone(), row.name.clone(), row.extracted_text.clone())) {
+            wrt_ref.serialize(row)?;
+        }
+    }
+    wrt_ref.flush()?;
+    Ok(())
 }
 
 pub async fn write_simple_cdx_csv(comp: &Components, csv_path: &String) -> Result<(), Box<dyn Error>>{
@@ -79,62 +393,129 @@ pub async fn write_simple_cdx_csv(comp: &Components, csv_path: &String) -> Resul
     let mut sbom_group = "";
     let mut sbom_version = "";
     let sbom_data = &comp.metadata;
-    if let Some(innerData) = &sbom_data.component{
-        if let Some(sbom_component)= innerData{
-            sbom_name = &sbom_component.name;
-            if let Some(innerGrp) = &sbom_component.group{
-                if let Some(group) = innerGrp{
-                    sbom_group = group;
-                }                
-            }
-            if let Some(innerVer) = &sbom_component.version{
-                if let Some(version) = innerVer{
-                    sbom_version = version;
-                }      
-            }
-        }   
-    }
-
-    for component in &comp.components{      
-        let package_name = component.name.clone();
-        let mut cpe_name = "";
-        if let Some(inner_cpe) = &component.cpe{
-            if let Some(cpe) = inner_cpe{
-                cpe_name = &cpe;
-            }
+    if let Some(Some(sbom_component)) = &sbom_data.component{
+        sbom_name = &sbom_component.name;
+        if let Some(Some(group)) = &sbom_component.group{
+            sbom_group = group;
+        }
+        if let Some(Some(version)) = &sbom_component.version{
+            sbom_version = version;
+        }
+    }
+    let sbom_name = sbom_name.to_string();
+    let sbom_group = sbom_group.to_string();
+    let sbom_version = sbom_version.to_string();
+    let empty = "".to_string();
+    // Product columns repeat on every row; the rest is set per row below.
+    let product_row = LicenseHeader{
+        product_name: &sbom_name,
+        namespace: &empty,
+        product_group: &sbom_group,
+        product_version: &sbom_version,
+        package_name: &empty,
+        group: &empty,
+        version: &empty,
+        component_type: &empty,
+        scope: &empty,
+        publisher: &empty,
+        author: &empty,
+        supplier: &empty,
+        package_reference: &empty,
+        package_identifier: &empty,
+        identifier_type: &empty,
+        license: &empty,
+        license_type: &empty,
+        alternate_reference_locator: &empty,
+        parent_reference: &empty,
+        component_path: &empty,
+        license_source: &empty,
+    };
+
+    // The product and BOM licenses come first, as a header section.
+    if let Some(Some(sbom_component)) = &sbom_data.component{
+        let purl = sbom_component.purl.clone().unwrap_or_default();
+        let (package_identifier, identifier_type) = sbom_component_identity(sbom_component);
+        let identifier_type = identifier_type.to_string();
+        let license_source = "metadata component".to_string();
+        for entry in sbom_component.licenses.iter().flatten().flatten(){
+            let (license_value, license_type) = license_entry_value(entry);
+            wtr.serialize(LicenseHeader{
+                package_name: &sbom_name,
+                group: &sbom_group,
+                version: &sbom_version,
+                package_reference: &purl,
+                package_identifier: &package_identifier,
+                identifier_type: &identifier_type,
+                license: &license_value,
+                license_type: &license_type,
+                component_path: &sbom_name,
+                license_source: &license_source,
+                ..product_row
+            })?;
+        }
+    }
+    let license_source = "bom".to_string();
+    for entry in sbom_data.licenses.iter().flatten().flatten(){
+        let (license_value, license_type) = license_entry_value(entry);
+        wtr.serialize(LicenseHeader{
+            license: &license_value,
+            license_type: &license_type,
+            license_source: &license_source,
+            ..product_row
+        })?;
+    }
+
+    let license_source = "component".to_string();
+    let mut identifier_counts: HashMap<&str, usize> = HashMap::new();
+    let mut nested = Vec::new();
+    flatten_components(&comp.components, None, &mut nested);
+    for NestedComponent{ component, parent_reference, path } in &nested{
+        let Some(Some(licenses)) = &component.licenses else {
+            continue;
+        };
+        let cpe_name = component.cpe.clone().flatten().unwrap_or_default();
+        let purl = component.purl.clone().unwrap_or_default();
+        let (package_identifier, identifier_type) = component_identity(component);
+        if !licenses.is_empty() {
+            *identifier_counts.entry(identifier_type).or_insert(0) += 1;
         }
-        if let Some(purl) = &component.purl{
-            if let Some(inner_licenses) = &component.licenses{
-                if let Some(licenses) = inner_licenses{
-
-                    for entry in licenses{
-                        let mut license_value = String::new();
-                        if let Some(license) = &entry.license{
-                            if let Some(id) = &license.id{
-                                license_value = id.clone();
-                            } else if let Some(name) = &license.name{
-                                license_value = name.clone();
-                            }
-                        }
-                        if let Some(expression) = &entry.expression{
-                            license_value = expression.clone();
-                        }
-                        let _ = wtr.serialize(LicenseHeader{
-                            name: &sbom_name.to_string(),
-                            namespace: &"".to_string(),
-                            group: &sbom_group.to_string(),
-                            version: &sbom_version.to_string(),
-                            package_reference: &purl.to_string(),
-                            license: &license_value,
-                            alternate_reference_locator: &cpe_name.to_string(),
-                            }
-                        );
-                    }
-                }
-            }
+        let identifier_type = identifier_type.to_string();
+        let group = component.group.clone().unwrap_or_default();
+        let version = component.version.clone().unwrap_or_default();
+        let component_type = component.component_type.clone().unwrap_or_default();
+        let scope = component.scope.clone().unwrap_or_default();
+        let publisher = component.publisher.clone().unwrap_or_default();
+        let author = component_author(component);
+        let supplier = component.supplier.as_ref().and_then(|s| s.name.clone()).unwrap_or_default();
+        for entry in licenses{
+            let (license_value, license_type) = license_entry_value(entry);
+            wtr.serialize(LicenseHeader{
+                package_name: &component.name,
+                group: &group,
+                version: &version,
+                component_type: &component_type,
+                scope: &scope,
+                publisher: &publisher,
+                author: &author,
+                supplier: &supplier,
+                package_reference: &purl,
+                package_identifier: &package_identifier,
+                identifier_type: &identifier_type,
+                license: &license_value,
+                license_type: &license_type,
+                alternate_reference_locator: &cpe_name,
+                parent_reference,
+                component_path: path,
+                license_source: &license_source,
+                ..product_row
+            })?;
         }
     }
     wtr.flush()?;
+    let counts: Vec<String> = IDENTIFIER_TYPES.iter()
+        .filter_map(|t| identifier_counts.get(t).map(|count| format!("{} {}", t, count)))
+        .collect();
+    info!("Exported {} component(s) by identifier type: {}", identifier_counts.values().sum::<usize>(), counts.join(", "));
     Ok(())
 }
 
@@ -237,3 +618,46 @@ pub async fn write_simple_cdx_csv(comp: &Components, csv_path: &String) -> Resul
 //     wtr.flush()?;
 //     Ok(())
 // }
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn metadata_and_components_share_the_identity_chain(){
+        let bom: Components = serde_json::from_str(r#"{
+            "metadata": {"component": {"name": "app", "version": "1.0", "cpe": "cpe:2.3:a:example:app:1.0:*:*:*:*:*:*:*"}},
+            "components": [
+                {"name": "app", "version": "1.0", "cpe": "cpe:2.3:a:example:app:1.0:*:*:*:*:*:*:*"},
+                {"name": "tagged", "swid": {"tagId": "example.com-tagged"}},
+                {"name": "plain", "version": "2", "bom-ref": ""}
+            ]
+        }"#).unwrap();
+        let product = bom.metadata.component.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(sbom_component_identity(product), component_identity(&bom.components[0]));
+        assert_eq!(sbom_component_identity(product).1, "cpe");
+        assert_eq!(component_identity(&bom.components[1]), ("example.com-tagged".to_string(), "swid"));
+        assert_eq!(component_identity(&bom.components[2]), ("plain@2".to_string(), "name@version"));
+    }
+
+    fn named(name: &str) -> License{
+        serde_json::from_value(serde_json::json!({"name": name})).unwrap()
+    }
+
+    #[test]
+    fn license_ref_ids_keep_sanitized_names_apart(){
+        let ids: HashSet<String> = ["Acme EULA", "Acme/EULA", "Acme-EULA"].iter()
+            .map(|name| license_ref_id(&named(name)).unwrap())
+            .collect();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.iter().all(|id| id.starts_with("LicenseRef-Acme-EULA-")));
+        assert_eq!(license_ref_id(&named("Acme EULA")), license_ref_id(&named("Acme EULA")));
+        assert_eq!(license_ref_id(&named(" ")), None);
+    }
+
+    #[test]
+    fn license_column_keeps_the_name(){
+        let entry: LicenseEntry = serde_json::from_str(r#"{"license": {"name": "Acme EULA"}}"#).unwrap();
+        assert_eq!(license_entry_value(&entry).0, "Acme EULA");
+    }
+}
@@ -0,0 +1,168 @@
+use crate::cdx_license::{component_identity, flatten_components, sbom_component_identity, license_entry_value, Components, LicenseEntry, Licensing};
+use chrono::{DateTime, NaiveDate, Utc};
+use csv::{QuoteStyle, WriterBuilder};
+use log::info;
+use serde_derive::Serialize;
+use std::error::Error;
+
+#[derive(Serialize, Debug)]
+pub struct CommercialLicenseHeader{
+    #[serde(rename = "package name")]
+    package_name: String,
+    #[serde(rename = "package version")]
+    package_version: String,
+    #[serde(rename = "package identifier")]
+    package_identifier: String,
+    license: String,
+    licensor: String,
+    licensee: String,
+    purchaser: String,
+    #[serde(rename = "purchase order")]
+    purchase_order: String,
+    #[serde(rename = "license types")]
+    license_types: String,
+    #[serde(rename = "alt ids")]
+    alt_ids: String,
+    #[serde(rename = "last renewal")]
+    last_renewal: String,
+    expiration: String,
+    #[serde(rename = "days until expiration")]
+    days_until_expiration: String,
+    #[serde(rename = "expiration status")]
+    expiration_status: String,
+}
+
+/// Parses an `expiration` date-time; a plain date is read as midnight UTC.
+fn parse_expiration(expiration: &str) -> Option<DateTime<Utc>>{
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(expiration) {
+        return Some(date_time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(expiration, "%Y-%m-%d").ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date_time| date_time.and_utc())
+}
+
+/// Returns the days left and one of `expired`, `expiring` (within
+/// `window_days`), `active`, `no expiration` or `invalid expiration`.
+fn expiration_status(expiration: Option<&str>, now: DateTime<Utc>, window_days: i64) -> (String, &'static str){
+    let Some(expiration) = expiration else {
+        return ("".to_string(), "no expiration");
+    };
+    let Some(expiration) = parse_expiration(expiration) else {
+        return ("".to_string(), "invalid expiration");
+    };
+    let days = (expiration - now).num_days();
+    let status = if expiration <= now {
+        "expired"
+    } else if days <= window_days {
+        "expiring"
+    } else {
+        "active"
+    };
+    (days.to_string(), status)
+}
+
+fn commercial_row(package_name: &str, package_version: &str, package_identifier: &str, entry: &LicenseEntry, licensing: &Licensing, now: DateTime<Utc>, window_days: i64) -> CommercialLicenseHeader{
+    let (license, _) = license_entry_value(entry);
+    let (days_until_expiration, status) = expiration_status(licensing.expiration.as_deref(), now, window_days);
+    CommercialLicenseHeader{
+        package_name: package_name.to_string(),
+        package_version: package_version.to_string(),
+        package_identifier: package_identifier.to_string(),
+        license,
+        licensor: licensing.licensor.as_ref().map(|l| l.name()).unwrap_or_default(),
+        licensee: licensing.licensee.as_ref().map(|l| l.name()).unwrap_or_default(),
+        purchaser: licensing.purchaser.as_ref().map(|p| p.name()).unwrap_or_default(),
+        purchase_order: licensing.purchase_order.clone().unwrap_or_default(),
+        license_types: licensing.license_types.clone().unwrap_or_default().join(", "),
+        alt_ids: licensing.alt_ids.clone().unwrap_or_default().join(", "),
+        last_renewal: licensing.last_renewal.clone().unwrap_or_default(),
+        expiration: licensing.expiration.clone().unwrap_or_default(),
+        days_until_expiration,
+        expiration_status: status.to_string(),
+    }
+}
+
+/// Writes one row per license that carries a `licensing` object, from the
+/// product, `metadata.licenses` and every (nested) component, and logs the
+/// licenses that are expired or expire within `window_days`.
+pub async fn write_commercial_csv(comp: &Components, commercial_csv_path: &String, window_days: i64) -> Result<(), Box<dyn Error>>{
+    let mut wtr = WriterBuilder::new()
+        .delimiter(b'\t')
+        .quote_style(QuoteStyle::Always)
+        .has_headers(false)
+        .from_path(commercial_csv_path)?;
+
+    wtr.write_record(["package name", "package version", "package identifier", "license", "licensor", "licensee", "purchaser", "purchase order", "license types", "alt ids", "last renewal", "expiration", "days until expiration", "expiration status"])?;
+
+    let now = Utc::now();
+    let mut rows = Vec::new();
+    if let Some(Some(product)) = &comp.metadata.component {
+        let version = product.version.clone().flatten().unwrap_or_default();
+        let (identifier, _) = sbom_component_identity(product);
+        for entry in product.licenses.iter().flatten().flatten(){
+            if let Some(licensing) = entry.license.as_ref().and_then(|l| l.licensing.as_ref()) {
+                rows.push(commercial_row(&product.name, &version, &identifier, entry, licensing, now, window_days));
+            }
+        }
+    }
+    for entry in comp.metadata.licenses.iter().flatten().flatten(){
+        if let Some(licensing) = entry.license.as_ref().and_then(|l| l.licensing.as_ref()) {
+            rows.push(commercial_row("", "", "", entry, licensing, now, window_days));
+        }
+    }
+    let mut nested = Vec::new();
+    flatten_components(&comp.components, None, &mut nested);
+    for component in nested.iter().map(|n| n.component){
+        let (identifier, _) = component_identity(component);
+        let version = component.version.clone().unwrap_or_default();
+        for entry in component.licenses.iter().flatten().flatten(){
+            if let Some(licensing) = entry.license.as_ref().and_then(|l| l.licensing.as_ref()) {
+                rows.push(commercial_row(&component.name, &version, &identifier, entry, licensing, now, window_days));
+            }
+        }
+    }
+
+    for row in &rows{
+        wtr.serialize(row)?;
+    }
+    wtr.flush()?;
+
+    let flagged: Vec<&CommercialLicenseHeader> = rows.iter()
+        .filter(|r| r.expiration_status == "expired" || r.expiration_status == "expiring")
+        .collect();
+    info!("{} commercial license(s), {} expired or expiring within {} day(s)", rows.len(), flagged.len(), window_days);
+    for row in flagged{
+        info!("  {} {} {}: {} ({})", row.expiration_status, row.package_name, row.package_version, row.license, row.expiration);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn today() -> DateTime<Utc>{
+        DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn flags_licenses_inside_the_window(){
+        assert_eq!(expiration_status(Some("2026-01-31"), today(), 30), ("30".to_string(), "expiring"));
+        assert_eq!(expiration_status(Some("2026-02-01"), today(), 30), ("31".to_string(), "active"));
+        assert_eq!(expiration_status(Some("2026-01-01T12:00:00+00:00"), today(), 0), ("0".to_string(), "expiring"));
+    }
+
+    #[test]
+    fn flags_expired_licenses(){
+        assert_eq!(expiration_status(Some("2026-01-01"), today(), 30), ("0".to_string(), "expired"));
+        assert_eq!(expiration_status(Some("2025-12-01T00:00:00Z"), today(), 30), ("-31".to_string(), "expired"));
+    }
+
+    #[test]
+    fn reports_missing_and_invalid_expirations(){
+        assert_eq!(expiration_status(None, today(), 30), ("".to_string(), "no expiration"));
+        assert_eq!(expiration_status(Some("31/01/2026"), today(), 30), ("".to_string(), "invalid expiration"));
+        assert_eq!(expiration_status(Some("2026-02-30"), today(), 30), ("".to_string(), "invalid expiration"));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::cdx_license::{AttachedText, Component, Components, License, LicenseEntry, Licensing, OrganizationalContact, OrganizationalEntity, OrganizationalEntityOrContact, SBOMComponent, SBOMMetadata, Swid};
+use roxmltree::{Document, Node};
+use std::error::Error;
+
+// Every schema version (1.2 to 1.6) shares this prefix, e.g. `http://cyclonedx.org/schema/bom/1.5`.
+const CDX_NS_PREFIX: &str = "http://cyclonedx.org/schema/bom/";
+
+/// Sniffs XML by the `.xml` extension or a leading `<`.
+pub fn is_cdx_xml(filepath: &str, content: &str) -> bool{
+    if filepath.ends_with(".xml") {
+        return true;
+    }
+    content.trim_start().starts_with('<')
+}
+
+fn is_cdx(node: &Node, name: &str) -> bool{
+    node.is_element()
+        && node.tag_name().name() == name
+        && node.tag_name().namespace().is_some_and(|ns| ns.starts_with(CDX_NS_PREFIX))
+}
+
+fn child<'a, 'input>(node: &Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>>{
+    node.children().find(|c| is_cdx(c, name))
+}
+
+fn child_text(node: &Node, name: &str) -> Option<String>{
+    child(node, name).and_then(|c| c.text()).map(|t| t.trim().to_string())
+}
+
+fn children_text(node: &Node, name: &str, item: &str) -> Option<Vec<String>>{
+    child(node, name).map(|list| list.children()
+        .filter(|c| is_cdx(c, item))
+        .filter_map(|c| c.text().map(|t| t.trim().to_string()))
+        .collect())
+}
+
+fn read_party(node: &Node, name: &str) -> Option<OrganizationalEntityOrContact>{
+    child(node, name).map(|party| OrganizationalEntityOrContact{
+        organization: child(&party, "organization").map(|o| OrganizationalEntity{ name: child_text(&o, "name") }),
+        individual: child(&party, "individual").map(|i| OrganizationalContact{ name: child_text(&i, "name") }),
+    })
+}
+
+/// Reads the `<licensing>` element of a commercial license.
+fn read_licensing(node: &Node) -> Option<Licensing>{
+    child(node, "licensing").map(|licensing| Licensing{
+        alt_ids: children_text(&licensing, "altIds", "altId"),
+        licensor: read_party(&licensing, "licensor"),
+        licensee: read_party(&licensing, "licensee"),
+        purchaser: read_party(&licensing, "purchaser"),
+        purchase_order: child_text(&licensing, "purchaseOrder"),
+        license_types: children_text(&licensing, "licenseTypes", "licenseType"),
+        last_renewal: child_text(&licensing, "lastRenewal"),
+        expiration: child_text(&licensing, "expiration"),
+    })
+}
+
+/// Reads a `<licenses>` element: each `<license>` and `<expression>` becomes one entry.
+fn read_licenses(node: &Node) -> Option<Option<Vec<LicenseEntry>>>{
+    let licenses = child(node, "licenses")?;
+    let entries = licenses.children()
+        .filter_map(|entry| {
+            if is_cdx(&entry, "license") {
+                Some(LicenseEntry{
+                    license: Some(License{
+                        bom_ref: entry.attribute("bom-ref").map(|r| r.to_string()),
+                        id: child_text(&entry, "id"),
+                        name: child_text(&entry, "name"),
+                        text: child(&entry, "text").map(|text| AttachedText{
+                            content: text.text().unwrap_or("").to_string(),
+                            content_type: text.attribute("content-type").map(|t| t.to_string()),
+                            encoding: text.attribute("encoding").map(|e| e.to_string()),
+                        }),
+                        url: child_text(&entry, "url"),
+                        acknowledgement: entry.attribute("acknowledgement").map(|a| a.to_string()),
+                        licensing: read_licensing(&entry),
+                    }),
+                    expression: None,
+                    acknowledgement: None,
+                })
+            } else if is_cdx(&entry, "expression") {
+                Some(LicenseEntry{
+                    license: None,
+                    expression: entry.text().map(|t| t.trim().to_string()),
+                    acknowledgement: entry.attribute("acknowledgement").map(|a| a.to_string()),
+                })
+            } else {
+                None
+            }
+        })
+        .collect();
+    Some(Some(entries))
+}
+
+fn read_components(node: &Node) -> Vec<Component>{
+    node.children()
+        .filter(|c| is_cdx(c, "component"))
+        .map(|component| Component{
+            bom_ref: component.attribute("bom-ref").map(|r| r.to_string()),
+            component_type: component.attribute("type").map(|t| t.to_string()),
+            supplier: child(&component, "supplier").map(|supplier| OrganizationalEntity{
+                name: child_text(&supplier, "name"),
+            }),
+            author: child_text(&component, "author"),
+            authors: child(&component, "authors").map(|authors| authors.children()
+                .filter(|a| is_cdx(a, "author"))
+                .map(|a| OrganizationalContact{ name: child_text(&a, "name") })
+                .collect()),
+            publisher: child_text(&component, "publisher"),
+            group: child_text(&component, "group"),
+            name: child_text(&component, "name").unwrap_or_default(),
+            version: child_text(&component, "version"),
+            scope: child_text(&component, "scope"),
+            licenses: read_licenses(&component),
+            purl: child_text(&component, "purl"),
+            cpe: child_text(&component, "cpe").map(Some),
+            swid: child(&component, "swid").map(|swid| Swid{
+                tag_id: swid.attribute("tagId").map(|t| t.to_string()),
+            }),
+            components: child(&component, "components").map(|c| read_components(&c)),
+        })
+        .collect()
+}
+
+/// Parses a `<bom>` in any CycloneDX schema namespace. The `bom-ref`, `type`,
+/// `acknowledgement` and `tagId` values are XML attributes here.
+pub fn parse_cdx_xml(content: &str) -> Result<Components, Box<dyn Error>>{
+    let doc = Document::parse(content)?;
+    let bom = doc.root_element();
+    if !is_cdx(&bom, "bom") {
+        return Err("root element is not a CycloneDX <bom>".into());
+    }
+    let metadata = child(&bom, "metadata");
+    let component = metadata.and_then(|m| child(&m, "component")).map(|component| SBOMComponent{
+        bom_ref: component.attribute("bom-ref").map(|r| r.to_string()),
+        group: Some(child_text(&component, "group")),
+        version: Some(child_text(&component, "version")),
+        name: child_text(&component, "name").unwrap_or_default(),
+        purl: child_text(&component, "purl"),
+        cpe: child_text(&component, "cpe").map(Some),
+        swid: child(&component, "swid").map(|swid| Swid{
+            tag_id: swid.attribute("tagId").map(|t| t.to_string()),
+        }),
+        licenses: read_licenses(&component),
+    });
+    Ok(Components{
+        metadata: SBOMMetadata{
+            component: component.map(Some),
+            licenses: metadata.and_then(|m| read_licenses(&m)),
+        },
+        components: child(&bom, "components").map(|c| read_components(&c)).unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const BOM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<bom xmlns="http://cyclonedx.org/schema/bom/1.6" version="1">
+  <metadata>
+    <component type="application" bom-ref="app">
+      <name>app</name>
+      <version>1.0</version>
+      <licenses><expression>Apache-2.0 OR MIT</expression></licenses>
+    </component>
+  </metadata>
+  <components>
+    <component type="library" bom-ref="pkg:maven/org.example/lib@2.0">
+      <group>org.example</group>
+      <name>lib</name>
+      <version>2.0</version>
+      <purl>pkg:maven/org.example/lib@2.0</purl>
+      <licenses>
+        <license acknowledgement="declared"><id>MIT</id><url>https://opensource.org/licenses/MIT</url></license>
+        <license><name>Custom</name><text content-type="text/plain" encoding="base64">Q3VzdG9t</text></license>
+        <expression acknowledgement="concluded">BSD-3-Clause</expression>
+      </licenses>
+      <components>
+        <component type="library"><name>inner</name><cpe>cpe:2.3:a:example:inner:1:*:*:*:*:*:*:*</cpe></component>
+      </components>
+    </component>
+  </components>
+</bom>"#;
+
+    #[test]
+    fn reads_licenses_and_expressions(){
+        let bom = parse_cdx_xml(BOM).unwrap();
+        let lib = &bom.components[0];
+        assert_eq!(lib.group.as_deref(), Some("org.example"));
+        assert_eq!(lib.purl.as_deref(), Some("pkg:maven/org.example/lib@2.0"));
+        let entries = lib.licenses.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(entries.len(), 3);
+
+        let mit = entries[0].license.as_ref().unwrap();
+        assert_eq!(mit.id.as_deref(), Some("MIT"));
+        assert_eq!(mit.url.as_deref(), Some("https://opensource.org/licenses/MIT"));
+        assert_eq!(mit.acknowledgement.as_deref(), Some("declared"));
+
+        let custom = entries[1].license.as_ref().unwrap();
+        assert_eq!(custom.name.as_deref(), Some("Custom"));
+        let text = custom.text.as_ref().unwrap();
+        assert_eq!(text.content, "Q3VzdG9t");
+        assert_eq!(text.encoding.as_deref(), Some("base64"));
+
+        assert!(entries[2].license.is_none());
+        assert_eq!(entries[2].expression.as_deref(), Some("BSD-3-Clause"));
+        assert_eq!(entries[2].acknowledgement.as_deref(), Some("concluded"));
+    }
+
+    #[test]
+    fn reads_metadata_and_nested_components(){
+        let bom = parse_cdx_xml(BOM).unwrap();
+        let product = bom.metadata.component.unwrap().unwrap();
+        assert_eq!(product.name, "app");
+        assert_eq!(product.version, Some(Some("1.0".to_string())));
+        let product_license = &product.licenses.unwrap().unwrap()[0];
+        assert_eq!(product_license.expression.as_deref(), Some("Apache-2.0 OR MIT"));
+
+        let inner = &bom.components[0].components.as_ref().unwrap()[0];
+        assert_eq!(inner.name, "inner");
+        assert_eq!(inner.cpe, Some(Some("cpe:2.3:a:example:inner:1:*:*:*:*:*:*:*".to_string())));
+    }
+
+    #[test]
+    fn accepts_older_schema_namespaces(){
+        let bom = parse_cdx_xml(r#"<bom xmlns="http://cyclonedx.org/schema/bom/1.2"><components>
+            <component type="library"><name>old</name><licenses><license><name>Legacy</name></license></licenses></component>
+        </components></bom>"#).unwrap();
+        assert_eq!(bom.components[0].name, "old");
+        assert!(bom.metadata.component.is_none());
+    }
+
+    #[test]
+    fn rejects_other_root_elements(){
+        assert!(parse_cdx_xml(r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"/>"#).is_err());
+        assert!(parse_cdx_xml(r#"<bom xmlns="http://example.com/not-cyclonedx"/>"#).is_err());
+    }
+}
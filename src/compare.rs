@@ -4,6 +4,14 @@ use std::error::Error;
 use std::fs;
 use std::path::Path;
 
+type LicenseKey = (String, String, String);
+
+/// NOASSERTION, NONE and empty values say nothing about the license, so they
+/// are counted separately instead of being compared as license identifiers.
+fn is_unasserted(license: &str) -> bool{
+    matches!(license.trim(), "" | "NOASSERTION" | "NONE")
+}
+
 fn find_file_matching(dir: &Path, suffix: &str) -> Option<String> {
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.flatten() {
@@ -16,37 +24,98 @@ fn find_file_matching(dir: &Path, suffix: &str) -> Option<String> {
     None
 }
 
+// Columns holding the purl, CPE, package name and version, in the order they
+// are tried. Our exports and trustify's name them differently, so the first
+// column present in a file is used.
+const PURL_COLUMNS: [&str; 2] = ["package reference", "package purl"];
+const CPE_COLUMNS: [&str; 4] = ["cpe23Type", "cpe22Type", "package cpe", "alternate package reference"];
+const NAME_COLUMNS: [&str; 1] = ["package name"];
+const VERSION_COLUMNS: [&str; 2] = ["package version", "version"];
+
+fn column(headers: &csv::StringRecord, names: &[&str]) -> Option<usize>{
+    names.iter().find_map(|c| headers.iter().position(|h| h == *c))
+}
+
+/// Reads `(package, type, license)` keys. Both sides of the comparison key a
+/// package the same way, from columns every export has: the purl, else a CPE,
+/// else `name@version`. Identifiers only our exports carry (SPDXID, bom-ref,
+/// checksum) are not used, as the comparator cannot match them.
 fn read_tsv_keys(
     path: &str,
-    purl_col: &str,
     license_col: &str,
     type_col: Option<&str>,
-) -> Result<HashSet<(String, String, String)>, Box<dyn Error>> {
-    let mut rdr = ReaderBuilder::new()
+) -> Result<(HashSet<LicenseKey>, usize), Box<dyn Error>> {
+    let rdr = ReaderBuilder::new()
         .delimiter(b'\t')
         .quoting(true)
         .from_path(path)?;
+    read_keys(rdr, path, license_col, type_col)
+}
+
+fn read_keys<R: std::io::Read>(
+    mut rdr: csv::Reader<R>,
+    path: &str,
+    license_col: &str,
+    type_col: Option<&str>,
+) -> Result<(HashSet<LicenseKey>, usize), Box<dyn Error>> {
     let headers = rdr.headers()?.clone();
-    let purl_idx = headers.iter().position(|h| h == purl_col)
-        .ok_or_else(|| format!("Column '{}' not found in {}", purl_col, path))?;
+    let purl_idx = column(&headers, &PURL_COLUMNS)
+        .ok_or_else(|| format!("Column '{}' not found in {}", PURL_COLUMNS.join("' or '"), path))?;
     let lic_idx = headers.iter().position(|h| h == license_col)
         .ok_or_else(|| format!("Column '{}' not found in {}", license_col, path))?;
+    let cpe_idxs: Vec<usize> = CPE_COLUMNS.iter()
+        .filter_map(|c| headers.iter().position(|h| h == *c))
+        .collect();
+    let name_idx = column(&headers, &NAME_COLUMNS);
+    let version_idx = column(&headers, &VERSION_COLUMNS);
     let type_idx = type_col.map(|tc| headers.iter().position(|h| h == tc)
         .ok_or_else(|| format!("Column '{}' not found in {}", tc, path)))
         .transpose()?;
 
+    // BOM-level license rows describe the SBOM document, not a package, and
+    // rows repeated for a package's additional purls have no counterpart in
+    // exports that keep one purl per package.
+    let source_idx = headers.iter().position(|h| h == "license source");
+    let identifier_type_idx = headers.iter().position(|h| h == "identifier type");
+
     let mut keys = HashSet::new();
+    let mut unasserted = 0;
     for result in rdr.records() {
         let record = result?;
-        let purl = record.get(purl_idx).unwrap_or("").to_string();
+        if source_idx.and_then(|i| record.get(i)) == Some("bom")
+            || identifier_type_idx.and_then(|i| record.get(i)) == Some("additional purl") {
+            continue;
+        }
+        let mut purl = record.get(purl_idx).unwrap_or("").to_string();
+        if purl.is_empty() {
+            purl = cpe_idxs.iter()
+                .filter_map(|i| record.get(*i))
+                .flat_map(|v| v.split(['\n', ';']).map(|v| v.trim()))
+                .find(|v| v.starts_with("cpe:"))
+                .unwrap_or("")
+                .to_string();
+        }
+        if purl.is_empty() {
+            let name = name_idx.and_then(|i| record.get(i)).unwrap_or("");
+            let version = version_idx.and_then(|i| record.get(i)).unwrap_or("");
+            purl = match (name, version) {
+                ("", _) => "".to_string(),
+                (name, "") => name.to_string(),
+                (name, version) => format!("{}@{}", name, version),
+            };
+        }
         let lic = record.get(lic_idx).unwrap_or("").to_string();
+        if is_unasserted(&lic) {
+            unasserted += 1;
+            continue;
+        }
         let ltype = type_idx
             .and_then(|i| record.get(i))
             .unwrap_or("")
             .to_string();
         keys.insert((purl, ltype, lic));
     }
-    Ok(keys)
+    Ok((keys, unasserted))
 }
 
 fn read_ref_keys(path: &str) -> Result<HashSet<(String, String)>, Box<dyn Error>> {
@@ -69,7 +138,7 @@ fn read_ref_keys(path: &str) -> Result<HashSet<(String, String)>, Box<dyn Error>
     Ok(keys)
 }
 
-fn print_license_diff(our_keys: &HashSet<(String, String, String)>, their_keys: &HashSet<(String, String, String)>) {
+fn print_license_diff(our_keys: &HashSet<LicenseKey>, their_keys: &HashSet<LicenseKey>) {
     let common = our_keys.intersection(their_keys).count();
     let only_ours: Vec<_> = our_keys.difference(their_keys).collect();
     let only_theirs: Vec<_> = their_keys.difference(our_keys).collect();
@@ -94,7 +163,7 @@ fn print_license_diff(our_keys: &HashSet<(String, String, String)>, their_keys:
     }
 }
 
-pub fn compare_licenses(our_csv: &str, our_ref_csv: Option<&str>, compare_dir: &str, sbom_type: &str) {
+pub fn compare_licenses(our_csv: &str, our_ref_csv: &str, compare_dir: &str) {
     let dir = Path::new(compare_dir);
     if !dir.is_dir() {
         println!("Compare path '{}' is not a directory", compare_dir);
@@ -109,14 +178,15 @@ pub fn compare_licenses(our_csv: &str, our_ref_csv: Option<&str>, compare_dir: &
         println!("Our file:   {}", our_csv);
         println!("Their file: {}", their_lic_path);
 
-        let our_result = read_tsv_keys(our_csv, "package reference", "license", None);
+        let our_result = read_tsv_keys(our_csv, "license", None);
 
-        let their_result = read_tsv_keys(their_lic_path, "package purl", "license", None);
+        let their_result = read_tsv_keys(their_lic_path, "license", None);
 
         match (our_result, their_result) {
-            (Ok(our_keys), Ok(their_keys)) => {
+            (Ok((our_keys, our_unasserted)), Ok((their_keys, their_unasserted))) => {
                 println!("  Our rows:   {}", our_keys.len());
                 println!("  Their rows: {}", their_keys.len());
+                println!("  Unasserted rows skipped (NOASSERTION/NONE/empty): ours {}, theirs {}", our_unasserted, their_unasserted);
                 print_license_diff(&our_keys, &their_keys);
             }
             (Err(e), _) => println!("  Error reading our file: {}", e),
@@ -126,36 +196,121 @@ pub fn compare_licenses(our_csv: &str, our_ref_csv: Option<&str>, compare_dir: &
         println!("\nNo *_sbom_licenses.csv found in {}", compare_dir);
     }
 
-    if sbom_type == "spdx" {
-        if let (Some(our_ref), Some(their_ref_path)) = (our_ref_csv, &their_ref) {
-            println!("\n=== License Ref Comparison ===");
-            println!("Our file:   {}", our_ref);
-            println!("Their file: {}", their_ref_path);
-
-            match (read_ref_keys(our_ref), read_ref_keys(their_ref_path)) {
-                (Ok(our_keys), Ok(their_keys)) => {
-                    let common = our_keys.intersection(&their_keys).count();
-                    let only_ours: Vec<_> = our_keys.difference(&their_keys).collect();
-                    let only_theirs: Vec<_> = their_keys.difference(&our_keys).collect();
-
-                    println!("  Our entries:   {}", our_keys.len());
-                    println!("  Their entries: {}", their_keys.len());
-                    println!("  Common: {}", common);
-                    println!("  Only in our output: {}", only_ours.len());
-                    println!("  Only in comparator: {}", only_theirs.len());
-
-                    for (id, name) in &only_ours {
-                        println!("    [ours] {} -> {}", id, name);
-                    }
-                    for (id, name) in &only_theirs {
-                        println!("    [theirs] {} -> {}", id, name);
-                    }
+    if let Some(their_ref_path) = &their_ref {
+        println!("\n=== License Ref Comparison ===");
+        println!("Our file:   {}", our_ref_csv);
+        println!("Their file: {}", their_ref_path);
+
+        match (read_ref_keys(our_ref_csv), read_ref_keys(their_ref_path)) {
+            (Ok(our_keys), Ok(their_keys)) => {
+                let common = our_keys.intersection(&their_keys).count();
+                let only_ours: Vec<_> = our_keys.difference(&their_keys).collect();
+                let only_theirs: Vec<_> = their_keys.difference(&our_keys).collect();
+
+                println!("  Our entries:   {}", our_keys.len());
+                println!("  Their entries: {}", their_keys.len());
+                println!("  Common: {}", common);
+                println!("  Only in our output: {}", only_ours.len());
+                println!("  Only in comparator: {}", only_theirs.len());
+
+                for (id, name) in &only_ours {
+                    println!("    [ours] {} -> {}", id, name);
+                }
+                for (id, name) in &only_theirs {
+                    println!("    [theirs] {} -> {}", id, name);
                 }
-                (Err(e), _) => println!("  Error reading our file: {}", e),
-                (_, Err(e)) => println!("  Error reading comparator file: {}", e),
             }
-        } else if our_ref_csv.is_some() && their_ref.is_none() {
-            println!("\nNo *_license_ref.csv found in {}", compare_dir);
+            (Err(e), _) => println!("  Error reading our file: {}", e),
+            (_, Err(e)) => println!("  Error reading comparator file: {}", e),
         }
+    } else {
+        println!("\nNo *_license_ref.csv found in {}", compare_dir);
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn keys(rows: &[&str]) -> (HashSet<LicenseKey>, usize){
+        let content = rows.join("\n");
+        let rdr = ReaderBuilder::new()
+            .delimiter(b'\t')
+            .from_reader(content.as_bytes());
+        read_keys(rdr, "test", "license", None).unwrap()
+    }
+
+    fn key(package: &str, license: &str) -> LicenseKey{
+        (package.to_string(), "".to_string(), license.to_string())
+    }
+
+    #[test]
+    fn prefers_the_purl(){
+        let (ours, _) = keys(&[
+            "package name\tpackage version\tpackage reference\tcpe23Type\tlicense",
+            "lib\t1.0\tpkg:npm/lib@1.0\tcpe:2.3:a:x:lib:1.0:*:*:*:*:*:*:*\tMIT",
+        ]);
+        let (theirs, _) = keys(&[
+            "package name\tpackage version\tpackage purl\tpackage cpe\tlicense",
+            "lib\t1.0\tpkg:npm/lib@1.0\tcpe:2.3:a:x:lib:1.0:*:*:*:*:*:*:*\tMIT",
+        ]);
+        assert_eq!(ours, HashSet::from([key("pkg:npm/lib@1.0", "MIT")]));
+        assert_eq!(ours, theirs);
+    }
+
+    #[test]
+    fn falls_back_to_cpe_then_name_and_version(){
+        let (ours, _) = keys(&[
+            "package name\tpackage version\tpackage reference\tpackage identifier\tcpe23Type\tlicense",
+            "a\t1.0\t\tSPDXRef-a a@1.0\tcpe:2.3:a:x:a:1.0:*:*:*:*:*:*:*\tMIT",
+            "b\t2.0\t\tSPDXRef-b b@2.0\t\tMIT",
+            "c\t\t\tSHA1:abcd\t\tMIT",
+        ]);
+        let (theirs, _) = keys(&[
+            "package name\tpackage version\tpackage purl\tpackage cpe\tlicense",
+            "a\t1.0\t\tcpe:2.3:a:x:a:1.0:*:*:*:*:*:*:*\tMIT",
+            "b\t2.0\t\t\tMIT",
+            "c\t\t\t\tMIT",
+        ]);
+        assert_eq!(ours, HashSet::from([
+            key("cpe:2.3:a:x:a:1.0:*:*:*:*:*:*:*", "MIT"),
+            key("b@2.0", "MIT"),
+            key("c", "MIT"),
+        ]));
+        assert_eq!(ours, theirs);
+    }
+
+    #[test]
+    fn reads_cyclonedx_version_column(){
+        let (ours, _) = keys(&[
+            "package name\tversion\tpackage reference\tpackage identifier\tidentifier type\tlicense",
+            "lib\t3\t\tlib-ref\tbom-ref\tMIT",
+        ]);
+        assert_eq!(ours, HashSet::from([key("lib@3", "MIT")]));
+    }
+
+    #[test]
+    fn skips_bom_and_additional_purl_rows(){
+        let (ours, unasserted) = keys(&[
+            "package name\tpackage reference\tidentifier type\tlicense source\tlicense",
+            "lib\tpkg:npm/lib@1\tpurl\tcomponent\tMIT",
+            "lib\tpkg:generic/lib@1\tadditional purl\tcomponent\tMIT",
+            "\t\t\tbom\tCC0-1.0",
+        ]);
+        assert_eq!(ours, HashSet::from([key("pkg:npm/lib@1", "MIT")]));
+        assert_eq!(unasserted, 0);
+    }
+
+    #[test]
+    fn counts_unasserted_licenses_instead_of_comparing_them(){
+        let (ours, unasserted) = keys(&[
+            "package name\tpackage reference\tlicense",
+            "a\tpkg:npm/a@1\tNOASSERTION",
+            "b\tpkg:npm/b@1\tNONE",
+            "c\tpkg:npm/c@1\t",
+            "d\tpkg:npm/d@1\tMIT",
+        ]);
+        assert_eq!(ours, HashSet::from([key("pkg:npm/d@1", "MIT")]));
+        assert_eq!(unasserted, 3);
     }
 }
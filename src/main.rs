@@ -1,7 +1,16 @@
 mod cdx_license;
+mod cdx_licensing;
+mod cdx_proto;
+mod cdx_xml;
 mod compare;
+mod spdx3_license;
+mod spdx_diagnostics;
+mod spdx_docref;
+mod spdx_graph;
 mod spdx_license;
-use clap::{Command, Arg};
+mod spdx_rdf;
+mod spdx_tag_value;
+use clap::{Command, Arg, ArgAction};
 use simplelog::*;
 
 #[tokio::main]
@@ -35,6 +44,14 @@ async fn main(){
                         .long("sbom_type")
                         .required(true)
                 )
+                .arg(
+                    Arg::new("sbom_format")
+                        .help("CycloneDX encoding: json, xml or protobuf (default: from the file extension)")
+                        .short('i')
+                        .long("sbom_format")
+                        .value_parser(["json", "xml", "protobuf"])
+                        .required(false)
+                )
                 .arg(
                     Arg::new("csv_path")
                         .help("SBOM License CSV Path")
@@ -44,11 +61,79 @@ async fn main(){
                 )
                 .arg(
                     Arg::new("ref_file_path")
-                        .help("SPDX SBOM License Reference Path")
+                        .help("SBOM License Reference Path")
                         .short('r')
                         .long("ref_file_path")
                         .required(false)
                 )
+                .arg(
+                    Arg::new("file_csv_path")
+                        .help("SPDX file-level license CSV Path")
+                        .short('f')
+                        .long("file_csv_path")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("snippet_csv_path")
+                        .help("SPDX snippet-level license CSV Path")
+                        .short('s')
+                        .long("snippet_csv_path")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("suppress_noassertion")
+                        .help("Skip SPDX license rows whose value is NOASSERTION")
+                        .long("suppress_noassertion")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("resolve_license_refs")
+                        .help("Add a column with SPDX LicenseRefs replaced by their extracted license names")
+                        .long("resolve_license_refs")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("attribution_columns")
+                        .help("Add SPDX supplier, originator, copyright text, download location and homepage columns")
+                        .long("attribution_columns")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("related_docs_path")
+                        .help("Directory with SPDX documents referenced through externalDocumentRefs")
+                        .short('d')
+                        .long("related_docs_path")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("metadata_path")
+                        .help("SPDX document metadata CSV Path")
+                        .short('m')
+                        .long("metadata_path")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("diagnostics_path")
+                        .help("SPDX parsing diagnostics CSV Path")
+                        .short('e')
+                        .long("diagnostics_path")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("commercial_csv_path")
+                        .help("CycloneDX commercial license CSV Path")
+                        .short('l')
+                        .long("commercial_csv_path")
+                        .required(false)
+                )
+                .arg(
+                    Arg::new("expiry_window_days")
+                        .help("Days ahead in which a commercial license counts as expiring")
+                        .short('w')
+                        .long("expiry_window_days")
+                        .value_parser(clap::value_parser!(i64))
+                        .default_value("30")
+                )
                 .arg(
                     Arg::new("compare_path")
                         .help("Directory with trustify license export to compare against")
@@ -65,18 +150,33 @@ async fn main(){
         .unwrap_or("license");
     let default_path = format!("{}_licenses.csv", sbom_stem);
     let csv_path = cli.get_one::<String>("csv_path").unwrap_or(&default_path);
+    let default_ref_path = format!("{}_license_ref.csv", sbom_stem);
+    let ref_file_path = cli.get_one::<String>("ref_file_path").unwrap_or(&default_ref_path);
     let compare_path = cli.get_one::<String>("compare_path");
     if sbom_type == "cdx"{
-        cdx_license::get_cdx_bom_license(sbom_file, csv_path).await;
+        let options = cdx_license::CdxExportOptions{
+            format: cli.get_one::<String>("sbom_format").cloned(),
+            commercial_csv_path: cli.get_one::<String>("commercial_csv_path").cloned(),
+            expiry_window_days: *cli.get_one::<i64>("expiry_window_days").unwrap(),
+        };
+        cdx_license::get_cdx_bom_license(sbom_file, csv_path, ref_file_path, &options).await;
         if let Some(cmp) = compare_path {
-            compare::compare_licenses(csv_path, None, cmp, sbom_type);
+            compare::compare_licenses(csv_path, ref_file_path, cmp);
         }
     } else if sbom_type == "spdx"{
-        let default_ref_path = format!("{}_license_ref.csv", sbom_stem);
-        let ref_file_path = cli.get_one::<String>("ref_file_path").unwrap_or(&default_ref_path);
-        spdx_license::get_spdx_bom_license(sbom_file, csv_path, ref_file_path).await;
+        let options = spdx_license::SpdxExportOptions{
+            file_csv_path: cli.get_one::<String>("file_csv_path").cloned(),
+            snippet_csv_path: cli.get_one::<String>("snippet_csv_path").cloned(),
+            suppress_noassertion: cli.get_flag("suppress_noassertion"),
+            resolve_license_refs: cli.get_flag("resolve_license_refs"),
+            attribution_columns: cli.get_flag("attribution_columns"),
+            related_docs_path: cli.get_one::<String>("related_docs_path").cloned(),
+            metadata_path: cli.get_one::<String>("metadata_path").cloned(),
+            diagnostics_path: cli.get_one::<String>("diagnostics_path").cloned(),
+        };
+        spdx_license::get_spdx_bom_license(sbom_file, csv_path, ref_file_path, &options).await;
         if let Some(cmp) = compare_path {
-            compare::compare_licenses(csv_path, Some(ref_file_path.as_str()), cmp, sbom_type);
+            compare::compare_licenses(csv_path, ref_file_path, cmp);
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,349 @@
+use crate::spdx_graph::relationship_type_name;
+use crate::spdx_license::{ChecksumObj, CreationInfo, HasLicenseInfo, LicenseInfo, PackageObj, Packages, ReferenceObj, RelationshipObj};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+
+/// SPDX 3.0 documents carry an `@context` pointing at `spdx.org/rdf/3.x`.
+pub fn is_spdx3(content: &str) -> bool{
+    content.contains("\"@context\"") && content.contains("spdx.org/rdf/3.")
+}
+
+fn str_field<'a>(element: &'a Value, key: &str) -> Option<&'a str>{
+    element.get(key).and_then(|v| v.as_str())
+}
+
+fn element_type(element: &Value) -> &str{
+    str_field(element, "type").or_else(|| str_field(element, "@type")).unwrap_or("")
+}
+
+fn element_id(element: &Value) -> &str{
+    str_field(element, "spdxId").or_else(|| str_field(element, "@id")).unwrap_or("")
+}
+
+/// Last segment of an element IRI, e.g. `MIT` for `https://spdx.org/licenses/MIT`.
+fn iri_id(iri: &str) -> String{
+    match iri.rsplit_once('#').or_else(|| iri.rsplit_once('/')) {
+        Some((_, id)) => id.to_string(),
+        None => iri.to_string(),
+    }
+}
+
+fn id_list(value: Option<&Value>) -> Vec<String>{
+    match value {
+        Some(Value::String(id)) => vec![id.to_string()],
+        Some(Value::Array(ids)) => ids.iter().filter_map(|v| v.as_str()).map(|v| v.to_string()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+struct Graph<'a>{
+    elements: HashMap<&'a str, &'a Value>,
+    // Custom license IRI -> LicenseRef id, taken from the expressions' customIdMap.
+    custom_ids: HashMap<String, String>,
+}
+
+impl Graph<'_>{
+    fn custom_license_id(&self, iri: &str) -> String{
+        match self.custom_ids.get(iri) {
+            Some(license_id) => license_id.to_string(),
+            None => iri_id(iri),
+        }
+    }
+
+    /// Resolves the target of a license relationship to an SPDX expression.
+    fn license_expression(&self, iri: &str) -> String{
+        let Some(element) = self.elements.get(iri) else {
+            return match iri_id(iri).as_str() {
+                "NoAssertionLicense" => "NOASSERTION".to_string(),
+                "NoneLicense" => "NONE".to_string(),
+                id => id.to_string(),
+            };
+        };
+        match element_type(element) {
+            "simplelicensing_LicenseExpression" => str_field(element, "simplelicensing_licenseExpression")
+                .unwrap_or("")
+                .to_string(),
+            "expandedlicensing_ConjunctiveLicenseSet" | "expandedlicensing_DisjunctiveLicenseSet" => {
+                let operator = if element_type(element) == "expandedlicensing_ConjunctiveLicenseSet" { " AND " } else { " OR " };
+                let members: Vec<String> = id_list(element.get("expandedlicensing_member"))
+                    .iter()
+                    .map(|member| {
+                        let expression = self.license_expression(member);
+                        if expression.contains(" AND ") || expression.contains(" OR ") {
+                            format!("({})", expression)
+                        } else {
+                            expression
+                        }
+                    })
+                    .collect();
+                members.join(operator)
+            }
+            "expandedlicensing_CustomLicense" | "simplelicensing_SimpleLicensingText" => self.custom_license_id(iri),
+            "expandedlicensing_NoAssertionLicense" => "NOASSERTION".to_string(),
+            "expandedlicensing_NoneLicense" => "NONE".to_string(),
+            _ => iri_id(iri),
+        }
+    }
+}
+
+/// Formats an Agent element the way SPDX 2.x writes suppliers, e.g. `Organization: Example Inc`.
+fn agent_name(graph: &Graph, iri: &str) -> String{
+    let Some(agent) = graph.elements.get(iri) else {
+        return iri.to_string();
+    };
+    let name = str_field(agent, "name").unwrap_or(iri);
+    match element_type(agent) {
+        "Organization" => format!("Organization: {}", name),
+        "Person" => format!("Person: {}", name),
+        "SoftwareAgent" | "Tool" => format!("Tool: {}", name),
+        _ => name.to_string(),
+    }
+}
+
+fn read_package(graph: &Graph, element: &Value) -> PackageObj{
+    let mut references = Vec::new();
+    if let Some(purl) = str_field(element, "software_packageUrl") {
+        references.push(ReferenceObj{
+            referenceCategory: "PACKAGE-MANAGER".to_string(),
+            referenceType: "purl".to_string(),
+            referenceLocator: purl.to_string(),
+        });
+    }
+    if let Some(Value::Array(identifiers)) = element.get("externalIdentifier") {
+        for identifier in identifiers {
+            let reference_type = match str_field(identifier, "externalIdentifierType").unwrap_or("") {
+                "cpe22" => "cpe22Type",
+                "cpe23" => "cpe23Type",
+                "packageUrl" => "purl",
+                other => other,
+            };
+            references.push(ReferenceObj{
+                referenceCategory: if reference_type.starts_with("cpe") { "SECURITY" } else { "OTHER" }.to_string(),
+                referenceType: reference_type.to_string(),
+                referenceLocator: str_field(identifier, "identifier").unwrap_or("").to_string(),
+            });
+        }
+    }
+    let checksums: Vec<ChecksumObj> = match element.get("verifiedUsing") {
+        Some(Value::Array(hashes)) => hashes.iter()
+            .filter(|h| element_type(h) == "Hash")
+            .map(|h| ChecksumObj{
+                algorithm: str_field(h, "algorithm").unwrap_or("").replace('_', "-").to_uppercase(),
+                checksumValue: str_field(h, "hashValue").unwrap_or("").to_string(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    PackageObj{
+        SPDXID: Some(element_id(element).to_string()),
+        name: str_field(element, "name").unwrap_or("").to_string(),
+        versionInfo: str_field(element, "software_packageVersion").map(|v| v.to_string()),
+        supplier: str_field(element, "suppliedBy").map(|s| agent_name(graph, s)),
+        originator: Some(id_list(element.get("originatedBy")).iter().map(|o| agent_name(graph, o)).collect::<Vec<_>>().join(", "))
+            .filter(|o| !o.is_empty()),
+        copyrightText: str_field(element, "software_copyrightText").map(|v| v.to_string()),
+        downloadLocation: str_field(element, "software_downloadLocation").map(|v| v.to_string()),
+        homepage: str_field(element, "software_homePage").map(|v| v.to_string()),
+        externalRefs: if references.is_empty() { None } else { Some(Some(references)) },
+        checksums: if checksums.is_empty() { None } else { Some(checksums) },
+        ..Default::default()
+    }
+}
+
+/// Parses an SPDX 3.0 JSON-LD document into the SPDX 2.x model, resolving the
+/// `hasDeclaredLicense`/`hasConcludedLicense` relationships onto each package.
+pub fn parse_spdx3(content: &str) -> Result<(Packages, HasLicenseInfo), Box<dyn Error>>{
+    let document: Value = serde_json::from_str(content)?;
+    let empty = Vec::new();
+    let graph_elements = document.get("@graph").and_then(|g| g.as_array()).unwrap_or(&empty);
+
+    let mut graph = Graph{
+        elements: HashMap::new(),
+        custom_ids: HashMap::new(),
+    };
+    for element in graph_elements {
+        graph.elements.insert(element_id(element), element);
+        if let Some(Value::Array(entries)) = element.get("simplelicensing_customIdMap") {
+            for entry in entries {
+                if let (Some(key), Some(value)) = (str_field(entry, "key"), str_field(entry, "value")) {
+                    graph.custom_ids.insert(value.to_string(), key.to_string());
+                }
+            }
+        }
+    }
+
+    let mut license_extract = HasLicenseInfo::default();
+    let mut packages = Packages::default();
+    let mut package_index: HashMap<&str, usize> = HashMap::new();
+    let mut licenses = Vec::new();
+    for element in graph_elements {
+        match element_type(element) {
+            "SpdxDocument" => {
+                license_extract.name = str_field(element, "name").unwrap_or("").to_string();
+                license_extract.documentNamespace = element_id(element).to_string();
+                license_extract.dataLicense = str_field(element, "dataLicense").map(|l| graph.license_expression(l));
+                let creation_info = element.get("creationInfo")
+                    .and_then(|c| c.as_str().and_then(|id| graph.elements.get(id).copied()).or(Some(c)));
+                if let Some(creation_info) = creation_info {
+                    license_extract.spdxVersion = str_field(creation_info, "specVersion").map(|v| format!("SPDX-{}", v));
+                    let mut creators: Vec<String> = id_list(creation_info.get("createdBy")).iter().map(|a| agent_name(&graph, a)).collect();
+                    creators.extend(id_list(creation_info.get("createdUsing")).iter().map(|t| agent_name(&graph, t)));
+                    license_extract.creationInfo = Some(CreationInfo{
+                        created: str_field(creation_info, "created").map(|c| c.to_string()),
+                        creators: if creators.is_empty() { None } else { Some(creators) },
+                        licenseListVersion: None,
+                    });
+                }
+                packages.documentDescribes = Some(id_list(element.get("rootElement")));
+            }
+            "software_Package" => {
+                package_index.insert(element_id(element), packages.packages.len());
+                packages.packages.push(read_package(&graph, element));
+            }
+            "expandedlicensing_CustomLicense" | "simplelicensing_SimpleLicensingText" => {
+                licenses.push(LicenseInfo{
+                    licenseId: graph.custom_license_id(element_id(element)),
+                    name: str_field(element, "name").unwrap_or("").to_string(),
+                    extractedText: str_field(element, "expandedlicensing_licenseText")
+                        .or_else(|| str_field(element, "simplelicensing_licenseText"))
+                        .unwrap_or("")
+                        .to_string(),
+                    comment: str_field(element, "comment").unwrap_or("").to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut relationships = Vec::new();
+    let relationship_types = ["Relationship", "software_LifecycleScopedRelationship"];
+    for relationship in graph_elements.iter().filter(|e| relationship_types.contains(&element_type(e))) {
+        let relationship_type = str_field(relationship, "relationshipType").unwrap_or("");
+        if relationship_type != "hasDeclaredLicense" && relationship_type != "hasConcludedLicense" {
+            let from = str_field(relationship, "from").unwrap_or("");
+            // A lifecycle scope (build, test, runtime, ...) is kept as a suffix,
+            // e.g. `DEPENDS_ON (build)`, so build-time edges stay distinguishable.
+            let relationship_type = match str_field(relationship, "software_scope") {
+                Some(scope) => format!("{} ({})", relationship_type_name(relationship_type), iri_id(scope)),
+                None => relationship_type_name(relationship_type),
+            };
+            for to in id_list(relationship.get("to")) {
+                relationships.push(RelationshipObj{
+                    spdxElementId: from.to_string(),
+                    relationshipType: relationship_type.clone(),
+                    relatedSpdxElement: to,
+                });
+            }
+            continue;
+        }
+        let Some(index) = str_field(relationship, "from").and_then(|from| package_index.get(from)) else {
+            continue;
+        };
+        let expressions: Vec<String> = id_list(relationship.get("to"))
+            .iter()
+            .map(|to| graph.license_expression(to))
+            .collect();
+        let expression = expressions.join(" AND ");
+        let package = &mut packages.packages[*index];
+        if relationship_type == "hasDeclaredLicense" {
+            package.licenseDeclared = Some(expression);
+        } else {
+            package.licenseConcluded = Some(expression);
+        }
+    }
+
+    if !relationships.is_empty() {
+        packages.relationships = Some(relationships);
+    }
+    if !licenses.is_empty() {
+        license_extract.hasExtractedLicensingInfos = Some(Some(licenses));
+    }
+    Ok((packages, license_extract))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const DOCUMENT: &str = r#"{
+  "@context": "https://spdx.org/rdf/3.0.1/spdx-context.jsonld",
+  "@graph": [
+    {"type": "CreationInfo", "@id": "_:creationinfo", "specVersion": "3.0.1", "created": "2024-01-01T00:00:00Z", "createdBy": ["urn:example:org"]},
+    {"type": "Organization", "spdxId": "urn:example:org", "name": "Example Inc"},
+    {"type": "SpdxDocument", "spdxId": "urn:example:doc", "name": "sample", "creationInfo": "_:creationinfo", "rootElement": ["urn:example:app"]},
+    {"type": "software_Package", "spdxId": "urn:example:app", "name": "app", "software_packageVersion": "1.0",
+     "software_packageUrl": "pkg:npm/app@1.0", "suppliedBy": "urn:example:org"},
+    {"type": "software_Package", "spdxId": "urn:example:lib", "name": "lib",
+     "externalIdentifier": [{"type": "ExternalIdentifier", "externalIdentifierType": "cpe23", "identifier": "cpe:2.3:a:example:lib:1:*:*:*:*:*:*:*"}]},
+    {"type": "simplelicensing_LicenseExpression", "spdxId": "urn:example:expr", "simplelicensing_licenseExpression": "MIT OR LicenseRef-custom",
+     "simplelicensing_customIdMap": [{"key": "LicenseRef-custom", "value": "urn:example:custom"}]},
+    {"type": "expandedlicensing_CustomLicense", "spdxId": "urn:example:custom", "name": "Custom License", "expandedlicensing_licenseText": "Custom terms"},
+    {"type": "expandedlicensing_DisjunctiveLicenseSet", "spdxId": "urn:example:set",
+     "expandedlicensing_member": ["https://spdx.org/licenses/Apache-2.0", "urn:example:custom"]},
+    {"type": "Relationship", "spdxId": "urn:example:r1", "from": "urn:example:app", "relationshipType": "hasDeclaredLicense", "to": ["urn:example:expr"]},
+    {"type": "Relationship", "spdxId": "urn:example:r2", "from": "urn:example:app", "relationshipType": "hasConcludedLicense", "to": ["urn:example:set"]},
+    {"type": "Relationship", "spdxId": "urn:example:r3", "from": "urn:example:lib", "relationshipType": "hasConcludedLicense",
+     "to": ["https://spdx.org/rdf/3.0.1/terms/Expanded/NoAssertionLicense"]},
+    {"type": "Relationship", "spdxId": "urn:example:r4", "from": "urn:example:app", "relationshipType": "dependsOn", "to": ["urn:example:lib"]},
+    {"type": "software_LifecycleScopedRelationship", "spdxId": "urn:example:r5", "from": "urn:example:app", "relationshipType": "dependsOn",
+     "to": ["urn:example:tool"], "software_scope": "build"}
+  ]
+}"#;
+
+    #[test]
+    fn detects_spdx3_context(){
+        assert!(is_spdx3(DOCUMENT));
+        assert!(!is_spdx3(r#"{"spdxVersion": "SPDX-2.3", "packages": []}"#));
+    }
+
+    #[test]
+    fn resolves_license_relationships(){
+        let (packages, _) = parse_spdx3(DOCUMENT).unwrap();
+        let app = &packages.packages[0];
+        assert_eq!(app.licenseDeclared.as_deref(), Some("MIT OR LicenseRef-custom"));
+        assert_eq!(app.licenseConcluded.as_deref(), Some("Apache-2.0 OR LicenseRef-custom"));
+        let lib = &packages.packages[1];
+        assert_eq!(lib.licenseDeclared, None);
+        assert_eq!(lib.licenseConcluded.as_deref(), Some("NOASSERTION"));
+    }
+
+    #[test]
+    fn reads_package_identifiers(){
+        let (packages, license_extract) = parse_spdx3(DOCUMENT).unwrap();
+        let app = &packages.packages[0];
+        assert_eq!(app.SPDXID.as_deref(), Some("urn:example:app"));
+        assert_eq!(app.versionInfo.as_deref(), Some("1.0"));
+        assert_eq!(app.supplier.as_deref(), Some("Organization: Example Inc"));
+        let purl = &app.externalRefs.as_ref().unwrap().as_ref().unwrap()[0];
+        assert_eq!((purl.referenceType.as_str(), purl.referenceLocator.as_str()), ("purl", "pkg:npm/app@1.0"));
+        let cpe = &packages.packages[1].externalRefs.as_ref().unwrap().as_ref().unwrap()[0];
+        assert_eq!(cpe.referenceType, "cpe23Type");
+
+        assert_eq!(license_extract.name, "sample");
+        assert_eq!(license_extract.spdxVersion.as_deref(), Some("SPDX-3.0.1"));
+        assert_eq!(packages.documentDescribes, Some(vec!["urn:example:app".to_string()]));
+    }
+
+    #[test]
+    fn keeps_dependency_relationships_and_custom_licenses(){
+        let (packages, license_extract) = parse_spdx3(DOCUMENT).unwrap();
+        let relationships = packages.relationships.unwrap();
+        assert_eq!(relationships.len(), 2);
+        assert_eq!(relationships[0].relationshipType, "DEPENDS_ON");
+        assert_eq!(relationships[0].relatedSpdxElement, "urn:example:lib");
+
+        let licenses = license_extract.hasExtractedLicensingInfos.unwrap().unwrap();
+        assert_eq!(licenses.len(), 1);
+        assert_eq!(licenses[0].licenseId, "LicenseRef-custom");
+        assert_eq!(licenses[0].extractedText, "Custom terms");
+    }
+
+    #[test]
+    fn keeps_lifecycle_scope_in_relationship_type(){
+        let (packages, _) = parse_spdx3(DOCUMENT).unwrap();
+        let relationships = packages.relationships.unwrap();
+        assert_eq!(relationships[1].relationshipType, "DEPENDS_ON (build)");
+        assert_eq!(relationships[1].relatedSpdxElement, "urn:example:tool");
+    }
+}
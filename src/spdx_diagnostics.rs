@@ -0,0 +1,271 @@
+use crate::spdx_license::{CreationInfo, ExternalDocumentRef, FileObj, HasLicenseInfo, LicenseInfo, PackageObj, Packages, RelationshipObj, SnippetObj};
+use csv::{QuoteStyle, WriterBuilder};
+use log::info;
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+
+// Required properties per element, from the SPDX 2.3 JSON schema.
+const DOCUMENT_REQUIRED: [&str; 6] = ["SPDXID", "creationInfo", "dataLicense", "name", "spdxVersion", "documentNamespace"];
+const PACKAGE_REQUIRED: [&str; 3] = ["SPDXID", "downloadLocation", "name"];
+const EXTERNAL_REF_REQUIRED: [&str; 3] = ["referenceCategory", "referenceLocator", "referenceType"];
+const FILE_REQUIRED: [&str; 3] = ["SPDXID", "checksums", "fileName"];
+const SNIPPET_REQUIRED: [&str; 4] = ["SPDXID", "name", "ranges", "snippetFromFile"];
+const RELATIONSHIP_REQUIRED: [&str; 3] = ["spdxElementId", "relationshipType", "relatedSpdxElement"];
+const LICENSE_REQUIRED: [&str; 2] = ["licenseId", "extractedText"];
+const DOCUMENT_REF_REQUIRED: [&str; 3] = ["externalDocumentId", "spdxDocument", "checksum"];
+
+/// A problem found in one element of the document. `error` means the element
+/// was skipped, `warning` that it was kept with the offending value defaulted.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Diagnostic{
+    pub pointer: String,
+    pub severity: String,
+    pub message: String,
+}
+
+fn warning(pointer: String, message: String) -> Diagnostic{
+    Diagnostic{ pointer, severity: "warning".to_string(), message }
+}
+
+fn error(pointer: String, message: String) -> Diagnostic{
+    Diagnostic{ pointer, severity: "error".to_string(), message }
+}
+
+/// Escapes a property name for use in a JSON pointer (RFC 6901).
+fn pointer_segment(property: &str) -> String{
+    property.replace('~', "~0").replace('/', "~1")
+}
+
+fn check_required(element: &Value, pointer: &str, required: &[&str], diagnostics: &mut Vec<Diagnostic>){
+    for property in required{
+        if element.get(property).is_none_or(|v| v.is_null()) {
+            diagnostics.push(warning(format!("{}/{}", pointer, property), format!("missing required property '{}'", property)));
+        }
+    }
+}
+
+/// Deserializes each element of the array at `key` on its own, so one bad
+/// element is reported and skipped instead of failing the whole document.
+fn read_elements<T: DeserializeOwned>(document: &Value, key: &str, required: &[&str], diagnostics: &mut Vec<Diagnostic>) -> Option<Vec<T>>{
+    let value = document.get(key)?;
+    let Some(elements) = value.as_array() else {
+        if !value.is_null() {
+            diagnostics.push(error(format!("/{}", key), "expected an array".to_string()));
+        }
+        return None;
+    };
+    let mut result = Vec::new();
+    for (i, element) in elements.iter().enumerate(){
+        let pointer = format!("/{}/{}", key, i);
+        check_required(element, &pointer, required, diagnostics);
+        match serde_json::from_value::<T>(element.clone()) {
+            Ok(element) => result.push(element),
+            Err(e) => match read_valid_properties(element, &pointer, diagnostics) {
+                Some(element) => result.push(element),
+                None => diagnostics.push(error(pointer, e.to_string())),
+            },
+        }
+    }
+    Some(result)
+}
+
+/// Retries an element that failed to deserialize without the properties that
+/// fail on their own, e.g. a numeric `versionInfo`. Each dropped property is
+/// reported; the element is kept when the rest of it is valid. List properties
+/// such as `externalRefs` are checked item by item, so one bad item does not
+/// drop the valid ones next to it.
+fn read_valid_properties<T: DeserializeOwned>(element: &Value, pointer: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<T>{
+    let properties = element.as_object()?;
+    let mut valid = serde_json::Map::new();
+    let mut dropped = Vec::new();
+    for (key, value) in properties{
+        let single = Value::Object(serde_json::Map::from_iter([(key.clone(), value.clone())]));
+        let property_pointer = format!("{}/{}", pointer, pointer_segment(key));
+        match (serde_json::from_value::<T>(single), value.as_array()) {
+            (Ok(_), _) => {
+                valid.insert(key.clone(), value.clone());
+            }
+            (Err(_), Some(items)) => {
+                let items = read_valid_items::<T>(key, items, &property_pointer, &mut dropped);
+                valid.insert(key.clone(), Value::Array(items));
+            }
+            (Err(e), None) => dropped.push(warning(property_pointer, format!("{}; property ignored", e))),
+        }
+    }
+    let element = serde_json::from_value::<T>(Value::Object(valid)).ok()?;
+    diagnostics.extend(dropped);
+    Some(element)
+}
+
+/// Keeps the items of the list property `key` that deserialize on their own.
+/// A dropped item is reported by the pointer of each of its properties that
+/// fails, e.g. `/packages/1/externalRefs/0/referenceLocator`.
+fn read_valid_items<T: DeserializeOwned>(key: &str, items: &[Value], pointer: &str, diagnostics: &mut Vec<Diagnostic>) -> Vec<Value>{
+    let parses = |item: Value| {
+        let single = Value::Object(serde_json::Map::from_iter([(key.to_string(), Value::Array(vec![item]))]));
+        serde_json::from_value::<T>(single).map(|_| ())
+    };
+    let mut valid = Vec::new();
+    for (i, item) in items.iter().enumerate(){
+        let Err(e) = parses(item.clone()) else {
+            valid.push(item.clone());
+            continue;
+        };
+        let item_pointer = format!("{}/{}", pointer, i);
+        let offending: Vec<Diagnostic> = item.as_object().into_iter().flatten()
+            .filter_map(|(property, value)| {
+                let single = Value::Object(serde_json::Map::from_iter([(property.clone(), value.clone())]));
+                parses(single).err().map(|e| warning(format!("{}/{}", item_pointer, pointer_segment(property)), format!("{}; item ignored", e)))
+            })
+            .collect();
+        if offending.is_empty() {
+            diagnostics.push(warning(item_pointer, format!("{}; item ignored", e)));
+        } else {
+            diagnostics.extend(offending);
+        }
+    }
+    valid
+}
+
+/// Reads a single top-level property, reporting and defaulting it on a type mismatch.
+fn read_property<T: DeserializeOwned>(document: &Value, key: &str, diagnostics: &mut Vec<Diagnostic>) -> Option<T>{
+    let value = document.get(key).filter(|v| !v.is_null())?;
+    match serde_json::from_value::<T>(value.clone()) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            diagnostics.push(warning(format!("/{}", key), e.to_string()));
+            None
+        }
+    }
+}
+
+/// Reads an SPDX 2.x JSON (or YAML) document leniently. Missing required
+/// properties and elements that do not match the model are collected as
+/// diagnostics, keyed by JSON pointer, rather than aborting the export.
+pub fn parse_lenient(document: &Value) -> Result<(Packages, HasLicenseInfo, Vec<Diagnostic>), Box<dyn Error>>{
+    if !document.is_object() {
+        return Err("expected an SPDX document object".into());
+    }
+    let mut diagnostics = Vec::new();
+    check_required(document, "", &DOCUMENT_REQUIRED, &mut diagnostics);
+    if let Some(Value::Array(packages)) = document.get("packages") {
+        for (i, package) in packages.iter().enumerate(){
+            if let Some(Value::Array(references)) = package.get("externalRefs") {
+                for (j, reference) in references.iter().enumerate(){
+                    check_required(reference, &format!("/packages/{}/externalRefs/{}", i, j), &EXTERNAL_REF_REQUIRED, &mut diagnostics);
+                }
+            }
+        }
+    }
+
+    let packages = Packages{
+        packages: read_elements::<PackageObj>(document, "packages", &PACKAGE_REQUIRED, &mut diagnostics).unwrap_or_default(),
+        files: read_elements::<FileObj>(document, "files", &FILE_REQUIRED, &mut diagnostics),
+        snippets: read_elements::<SnippetObj>(document, "snippets", &SNIPPET_REQUIRED, &mut diagnostics),
+        relationships: read_elements::<RelationshipObj>(document, "relationships", &RELATIONSHIP_REQUIRED, &mut diagnostics),
+        documentDescribes: read_property(document, "documentDescribes", &mut diagnostics),
+    };
+    let license_extract = HasLicenseInfo{
+        hasExtractedLicensingInfos: read_elements::<LicenseInfo>(document, "hasExtractedLicensingInfos", &LICENSE_REQUIRED, &mut diagnostics)
+            .map(Some),
+        externalDocumentRefs: read_elements::<ExternalDocumentRef>(document, "externalDocumentRefs", &DOCUMENT_REF_REQUIRED, &mut diagnostics),
+        documentNamespace: read_property(document, "documentNamespace", &mut diagnostics).unwrap_or_default(),
+        name: read_property(document, "name", &mut diagnostics).unwrap_or_default(),
+        spdxVersion: read_property(document, "spdxVersion", &mut diagnostics),
+        dataLicense: read_property(document, "dataLicense", &mut diagnostics),
+        creationInfo: read_property::<CreationInfo>(document, "creationInfo", &mut diagnostics),
+    };
+    Ok((packages, license_extract, diagnostics))
+}
+
+/// Logs a short summary of the diagnostics of a document.
+pub fn log_diagnostics(filepath: &str, diagnostics: &[Diagnostic]){
+    if diagnostics.is_empty() {
+        return;
+    }
+    let errors = diagnostics.iter().filter(|d| d.severity == "error").count();
+    info!("{}: {} problem(s) found while parsing, {} element(s) skipped", filepath, diagnostics.len(), errors);
+    for (i, diagnostic) in diagnostics.iter().enumerate(){
+        if i >= 10 {
+            info!("  ... and {} more", diagnostics.len() - 10);
+            break;
+        }
+        info!("  {} {}: {}", diagnostic.severity, diagnostic.pointer, diagnostic.message);
+    }
+}
+
+pub async fn write_diagnostics_csv(diagnostics: &[Diagnostic], diagnostics_path: &String) -> Result<(), Box<dyn Error>>{
+    let mut wtr = WriterBuilder::new()
+        .delimiter(b'\t')
+        .quote_style(QuoteStyle::Always)
+        .has_headers(false)
+        .from_path(diagnostics_path)?;
+
+    wtr.write_record(["pointer", "severity", "message"])?;
+    for diagnostic in diagnostics{
+        wtr.serialize(diagnostic)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use serde_json::json;
+
+    fn document(packages: Value) -> Value{
+        json!({
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "name": "sample",
+            "documentNamespace": "https://example.com/sample",
+            "creationInfo": {"created": "2024-01-01T00:00:00Z", "creators": ["Tool: test"]},
+            "packages": packages,
+        })
+    }
+
+    #[test]
+    fn keeps_valid_items_next_to_a_bad_one(){
+        let (packages, _, diagnostics) = parse_lenient(&document(json!([
+            {"SPDXID": "SPDXRef-a", "name": "a", "downloadLocation": "NOASSERTION"},
+            {"SPDXID": "SPDXRef-b", "name": "b", "downloadLocation": "NOASSERTION", "externalRefs": [
+                {"referenceCategory": "PACKAGE-MANAGER", "referenceType": "npm", "referenceLocator": 5},
+                {"referenceCategory": "PACKAGE-MANAGER", "referenceType": "purl", "referenceLocator": "pkg:npm/b@1"},
+            ]},
+        ]))).unwrap();
+        let references = packages.packages[1].externalRefs.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].referenceLocator, "pkg:npm/b@1");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].pointer, "/packages/1/externalRefs/0/referenceLocator");
+        assert_eq!(diagnostics[0].severity, "warning");
+    }
+
+    #[test]
+    fn drops_a_bad_scalar_property_and_keeps_the_element(){
+        let (packages, _, diagnostics) = parse_lenient(&document(json!([
+            {"SPDXID": "SPDXRef-a", "name": "a", "downloadLocation": "NOASSERTION", "versionInfo": 2},
+        ]))).unwrap();
+        assert_eq!(packages.packages[0].name, "a");
+        assert_eq!(packages.packages[0].versionInfo, None);
+        assert_eq!(diagnostics[0].pointer, "/packages/0/versionInfo");
+    }
+
+    #[test]
+    fn reports_missing_required_properties(){
+        let (packages, _, diagnostics) = parse_lenient(&document(json!([{"name": "a"}]))).unwrap();
+        assert_eq!(packages.packages.len(), 1);
+        let pointers: Vec<&str> = diagnostics.iter().map(|d| d.pointer.as_str()).collect();
+        assert_eq!(pointers, ["/packages/0/SPDXID", "/packages/0/downloadLocation"]);
+    }
+
+    #[test]
+    fn escapes_pointer_segments(){
+        assert_eq!(pointer_segment("a/b~c"), "a~1b~0c");
+        assert_eq!(pointer_segment("externalRefs"), "externalRefs");
+    }
+}
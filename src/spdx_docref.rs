@@ -0,0 +1,283 @@
+use crate::spdx_diagnostics::log_diagnostics;
+use crate::spdx_license::{parse_spdx_document, ExternalDocumentRef, HasLicenseInfo, Packages, RelationshipObj, LICENSE_REF_PATTERN};
+use log::info;
+use regex::Regex;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+
+struct RelatedDocument{
+    path: String,
+    sha1: String,
+    packages: Packages,
+    license_extract: HasLicenseInfo,
+}
+
+/// Reads every SPDX document in the directory; files that are not SPDX are skipped.
+fn load_documents(dir: &str) -> Vec<RelatedDocument>{
+    let mut documents = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        info!("Related documents path '{}' is not a directory", dir);
+        return documents;
+    };
+    let mut paths: Vec<_> = entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect();
+    paths.sort();
+    for path in paths{
+        let path_str = path.to_string_lossy().to_string();
+        let Ok(bytes) = fs::read(&path) else {
+            continue;
+        };
+        let Ok(content) = String::from_utf8(bytes) else {
+            continue;
+        };
+        match parse_spdx_document(&path_str, &content) {
+            Ok((packages, license_extract, diagnostics)) => {
+                log_diagnostics(&path_str, &diagnostics);
+                documents.push(RelatedDocument{
+                    sha1: sha1_smol::Sha1::from(content.as_bytes()).digest().to_string(),
+                    path: path_str,
+                    packages,
+                    license_extract,
+                });
+            }
+            Err(e) => info!("Skipping related document {}: {}", path_str, e),
+        }
+    }
+    documents
+}
+
+/// Finds the document an external reference points at, by `documentNamespace`
+/// first and by the SHA1 checksum of the file otherwise.
+fn find_document(documents: &[RelatedDocument], reference: &ExternalDocumentRef) -> Option<usize>{
+    let checksum = reference.checksum.as_ref()
+        .filter(|c| c.algorithm == "SHA1")
+        .map(|c| c.checksumValue.to_lowercase());
+    if let Some(index) = documents.iter().position(|d| d.license_extract.documentNamespace == reference.spdxDocument) {
+        if let Some(checksum) = &checksum {
+            if *checksum != documents[index].sha1 {
+                info!("{} matches {} by namespace but its SHA1 checksum differs", documents[index].path, reference.externalDocumentId);
+            }
+        }
+        return Some(index);
+    }
+    let checksum = checksum?;
+    documents.iter().position(|d| d.sha1 == checksum)
+}
+
+/// Loads the SPDX documents in `dir` that the main document reaches through
+/// `externalDocumentRefs`, directly or through another related document, and
+/// merges their packages, files, snippets, relationships and extracted licenses
+/// into the main model.
+///
+/// Element ids and LicenseRefs of a related document are qualified with the
+/// DocumentRef id it is known by (e.g. `DocumentRef-component:LicenseRef-7`), so
+/// references from the main document resolve against the merged data.
+pub fn merge_related_documents(packages: &mut Packages, license_extract: &mut HasLicenseInfo, dir: &str){
+    let mut documents = load_documents(dir);
+    documents.retain(|d| d.license_extract.documentNamespace != license_extract.documentNamespace);
+
+    // Document index -> the DocumentRef id its elements are qualified with.
+    let mut qualifiers: HashMap<usize, String> = HashMap::new();
+    // Document index -> its own DocumentRef ids, mapped to document indexes.
+    let mut local_refs: HashMap<usize, HashMap<String, usize>> = HashMap::new();
+    let mut used_qualifiers: HashSet<String> = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    for reference in license_extract.externalDocumentRefs.iter().flatten(){
+        match find_document(&documents, reference) {
+            Some(index) => {
+                if let Entry::Vacant(entry) = qualifiers.entry(index) {
+                    entry.insert(reference.externalDocumentId.to_string());
+                    used_qualifiers.insert(reference.externalDocumentId.to_string());
+                    queue.push_back(index);
+                }
+            }
+            None => info!("{} ({}) was not found in {}", reference.externalDocumentId, reference.spdxDocument, dir),
+        }
+    }
+    while let Some(index) = queue.pop_front(){
+        let references: Vec<(String, Option<usize>)> = documents[index].license_extract.externalDocumentRefs.iter().flatten()
+            .map(|r| (r.externalDocumentId.to_string(), find_document(&documents, r)))
+            .collect();
+        for (document_ref, target) in references{
+            let Some(target) = target else {
+                continue;
+            };
+            local_refs.entry(index).or_default().insert(document_ref.clone(), target);
+            if qualifiers.contains_key(&target) {
+                continue;
+            }
+            // DocumentRef ids are only unique within one document.
+            let mut qualifier = document_ref.clone();
+            if used_qualifiers.contains(&qualifier) {
+                qualifier = format!("{}-{}", document_ref, target);
+            }
+            used_qualifiers.insert(qualifier.clone());
+            qualifiers.insert(target, qualifier);
+            queue.push_back(target);
+        }
+    }
+
+    let license_ref_re = Regex::new(LICENSE_REF_PATTERN).unwrap();
+    let mut merged: Vec<usize> = qualifiers.keys().copied().collect();
+    merged.sort();
+    let mut merged_packages = 0;
+    for index in &merged{
+        let document = &mut documents[*index];
+        let qualifier = &qualifiers[index];
+        let no_refs = HashMap::new();
+        let document_refs = local_refs.get(index).unwrap_or(&no_refs);
+        // Maps an id local to the related document into the merged id space.
+        let qualify = |id: &str| -> String {
+            if id == "NONE" || id == "NOASSERTION" {
+                return id.to_string();
+            }
+            if let Some((document_ref, element)) = id.split_once(':') {
+                if let Some(target) = document_refs.get(document_ref) {
+                    return format!("{}:{}", qualifiers[target], element);
+                }
+                return id.to_string();
+            }
+            format!("{}:{}", qualifier, id)
+        };
+        let rewrite = |expression: &str| -> String {
+            license_ref_re.replace_all(expression, |caps: &regex::Captures| qualify(&caps[0])).to_string()
+        };
+
+        let source = (document.license_extract.name.to_string(), document.license_extract.documentNamespace.to_string());
+        for mut package in std::mem::take(&mut document.packages.packages){
+            package.SPDXID = package.SPDXID.as_deref().map(qualify);
+            package.licenseDeclared = package.licenseDeclared.as_deref().map(rewrite);
+            package.licenseConcluded = package.licenseConcluded.as_deref().map(rewrite);
+            package.licenseInfoFromFiles = package.licenseInfoFromFiles.map(|l| l.iter().map(|id| rewrite(id)).collect());
+            package.hasFiles = package.hasFiles.map(|f| f.iter().map(|id| qualify(id)).collect());
+            package.sourceDocument = Some(source.clone());
+            packages.packages.push(package);
+            merged_packages += 1;
+        }
+        for mut file in document.packages.files.take().into_iter().flatten(){
+            file.SPDXID = qualify(&file.SPDXID);
+            file.licenseConcluded = file.licenseConcluded.as_deref().map(rewrite);
+            file.licenseInfoInFiles = file.licenseInfoInFiles.map(|l| l.iter().map(|id| rewrite(id)).collect());
+            packages.files.get_or_insert(Vec::new()).push(file);
+        }
+        for mut snippet in document.packages.snippets.take().into_iter().flatten(){
+            snippet.SPDXID = qualify(&snippet.SPDXID);
+            snippet.snippetFromFile = qualify(&snippet.snippetFromFile);
+            snippet.licenseConcluded = snippet.licenseConcluded.as_deref().map(rewrite);
+            snippet.licenseInfoInSnippets = snippet.licenseInfoInSnippets.map(|l| l.iter().map(|id| rewrite(id)).collect());
+            packages.snippets.get_or_insert(Vec::new()).push(snippet);
+        }
+        for mut relationship in document.packages.relationships.take().into_iter().flatten(){
+            relationship.spdxElementId = qualify(&relationship.spdxElementId);
+            relationship.relatedSpdxElement = qualify(&relationship.relatedSpdxElement);
+            packages.relationships.get_or_insert(Vec::new()).push(relationship);
+        }
+        for root in document.packages.documentDescribes.take().into_iter().flatten(){
+            packages.relationships.get_or_insert(Vec::new()).push(RelationshipObj{
+                spdxElementId: qualify("SPDXRef-DOCUMENT"),
+                relationshipType: "DESCRIBES".to_string(),
+                relatedSpdxElement: qualify(&root),
+            });
+        }
+        if let Some(Some(licenses)) = document.license_extract.hasExtractedLicensingInfos.take(){
+            let merged_licenses = license_extract.hasExtractedLicensingInfos.get_or_insert(Some(Vec::new())).get_or_insert(Vec::new());
+            for mut license in licenses{
+                license.licenseId = qualify(&license.licenseId);
+                merged_licenses.push(license);
+            }
+        }
+        info!("Merged related document {} as {}", document.path, qualifier);
+    }
+    if !merged.is_empty() {
+        info!("Merged {} related document(s) with {} package(s)", merged.len(), merged_packages);
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::spdx_license::ChecksumObj;
+    use std::path::PathBuf;
+
+    const RELATED: &str = "SPDXVersion: SPDX-2.3
+DocumentName: component
+DocumentNamespace: https://example.com/component
+PackageName: lib
+SPDXID: SPDXRef-lib
+PackageLicenseDeclared: MIT AND LicenseRef-1
+PackageLicenseConcluded: NOASSERTION
+Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-lib
+Relationship: SPDXRef-lib DEPENDS_ON NOASSERTION
+LicenseID: LicenseRef-1
+ExtractedText: Custom terms
+LicenseName: Custom
+";
+
+    fn related_dir(name: &str) -> PathBuf{
+        let dir = std::env::temp_dir().join(format!("sbom-docref-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("component.spdx"), RELATED).unwrap();
+        dir
+    }
+
+    fn main_document(spdx_document: &str, checksum: Option<&str>) -> (Packages, HasLicenseInfo){
+        let license_extract = HasLicenseInfo{
+            documentNamespace: "https://example.com/main".to_string(),
+            externalDocumentRefs: Some(vec![ExternalDocumentRef{
+                externalDocumentId: "DocumentRef-comp".to_string(),
+                spdxDocument: spdx_document.to_string(),
+                checksum: checksum.map(|c| ChecksumObj{ algorithm: "SHA1".to_string(), checksumValue: c.to_string() }),
+            }]),
+            ..Default::default()
+        };
+        (Packages::default(), license_extract)
+    }
+
+    #[test]
+    fn matches_by_namespace_and_qualifies_ids(){
+        let dir = related_dir("namespace");
+        let (mut packages, mut license_extract) = main_document("https://example.com/component", Some("0000"));
+        merge_related_documents(&mut packages, &mut license_extract, dir.to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        let package = &packages.packages[0];
+        assert_eq!(package.SPDXID.as_deref(), Some("DocumentRef-comp:SPDXRef-lib"));
+        assert_eq!(package.licenseDeclared.as_deref(), Some("MIT AND DocumentRef-comp:LicenseRef-1"));
+        assert_eq!(package.licenseConcluded.as_deref(), Some("NOASSERTION"));
+        assert_eq!(package.sourceDocument, Some(("component".to_string(), "https://example.com/component".to_string())));
+
+        let relationships = packages.relationships.unwrap();
+        assert_eq!(relationships[0].spdxElementId, "DocumentRef-comp:SPDXRef-DOCUMENT");
+        assert_eq!(relationships[0].relatedSpdxElement, "DocumentRef-comp:SPDXRef-lib");
+        assert_eq!(relationships[1].spdxElementId, "DocumentRef-comp:SPDXRef-lib");
+        assert_eq!(relationships[1].relatedSpdxElement, "NOASSERTION");
+
+        let licenses = license_extract.hasExtractedLicensingInfos.unwrap().unwrap();
+        assert_eq!(licenses[0].licenseId, "DocumentRef-comp:LicenseRef-1");
+    }
+
+    #[test]
+    fn falls_back_to_sha1_checksum(){
+        let dir = related_dir("sha1");
+        let sha1 = sha1_smol::Sha1::from(RELATED.as_bytes()).digest().to_string().to_uppercase();
+        let (mut packages, mut license_extract) = main_document("https://example.com/renamed", Some(&sha1));
+        merge_related_documents(&mut packages, &mut license_extract, dir.to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(packages.packages.len(), 1);
+        assert_eq!(packages.packages[0].SPDXID.as_deref(), Some("DocumentRef-comp:SPDXRef-lib"));
+    }
+
+    #[test]
+    fn leaves_unmatched_references_alone(){
+        let dir = related_dir("missing");
+        let (mut packages, mut license_extract) = main_document("https://example.com/unknown", Some("0000"));
+        merge_related_documents(&mut packages, &mut license_extract, dir.to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(packages.packages.is_empty());
+        assert!(packages.relationships.is_none());
+        assert!(license_extract.hasExtractedLicensingInfos.is_none());
+    }
+}
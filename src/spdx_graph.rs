@@ -0,0 +1,139 @@
+use crate::spdx_license::Packages;
+use std::collections::{HashMap, VecDeque};
+
+const DOCUMENT_ID: &str = "SPDXRef-DOCUMENT";
+
+/// Where a package sits in the dependency graph, as seen from the described root.
+#[derive(Debug, Clone)]
+pub struct GraphEntry{
+    pub parent: String,
+    pub depth: usize,
+    pub relationship_type: String,
+}
+
+/// Converts RDF and SPDX 3.0 relationship names such as `dependsOn` or
+/// `relationshipType_dependsOn` to the SPDX 2.x JSON form `DEPENDS_ON`.
+pub fn relationship_type_name(name: &str) -> String{
+    let name = name.rsplit('_').next().unwrap_or(name);
+    let mut type_name = String::new();
+    for c in name.chars(){
+        if c.is_uppercase() {
+            type_name.push('_');
+        }
+        type_name.push(c.to_ascii_uppercase());
+    }
+    type_name
+}
+
+/// Orients a relationship as `(parent, child)`. Types ending in `_OF`/`_BY`
+/// point from child to parent, so they are flipped. Relationships that do not
+/// describe containment or dependency are ignored. A lifecycle scope suffix
+/// such as `DEPENDS_ON (build)` does not change the direction.
+fn edge<'a>(element: &'a str, relationship_type: &str, related: &'a str) -> Option<(&'a str, &'a str)>{
+    match relationship_type.split(" (").next().unwrap_or(relationship_type) {
+        "DESCRIBES" | "CONTAINS" | "DEPENDS_ON" | "HAS_PREREQUISITE" | "STATIC_LINK" | "DYNAMIC_LINK" => Some((element, related)),
+        "DESCRIBED_BY" | "CONTAINED_BY" | "DEPENDENCY_OF" | "DEV_DEPENDENCY_OF" | "BUILD_DEPENDENCY_OF"
+        | "TEST_DEPENDENCY_OF" | "RUNTIME_DEPENDENCY_OF" | "OPTIONAL_DEPENDENCY_OF" | "PROVIDED_DEPENDENCY_OF"
+        | "PREREQUISITE_FOR" | "PACKAGE_OF" | "DEV_TOOL_OF" | "BUILD_TOOL_OF" => Some((related, element)),
+        _ => None,
+    }
+}
+
+/// Walks `relationships[]` breadth-first from the packages the document
+/// describes, keyed by package SPDXID. Each package gets the shortest path
+/// from a root; packages that cannot be reached are left out.
+pub fn dependency_graph(packages: &Packages) -> HashMap<String, GraphEntry>{
+    let mut children: HashMap<&str, Vec<(&str, &str)>> = HashMap::new();
+    for relationship in packages.relationships.iter().flatten(){
+        if let Some((parent, child)) = edge(&relationship.spdxElementId, &relationship.relationshipType, &relationship.relatedSpdxElement){
+            children.entry(parent).or_default().push((child, &relationship.relationshipType));
+        }
+    }
+    for root in packages.documentDescribes.iter().flatten(){
+        children.entry(DOCUMENT_ID).or_default().push((root, "DESCRIBES"));
+    }
+
+    let mut graph: HashMap<String, GraphEntry> = HashMap::new();
+    let mut queue = VecDeque::from([(DOCUMENT_ID, 0)]);
+    while let Some((parent, depth)) = queue.pop_front(){
+        for (child, relationship_type) in children.get(parent).into_iter().flatten(){
+            if *child == DOCUMENT_ID || graph.contains_key(*child){
+                continue;
+            }
+            graph.insert(child.to_string(), GraphEntry{
+                parent: parent.to_string(),
+                depth,
+                relationship_type: relationship_type.to_string(),
+            });
+            queue.push_back((child, depth + 1));
+        }
+    }
+    graph
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::spdx_license::RelationshipObj;
+
+    fn relationship(element: &str, relationship_type: &str, related: &str) -> RelationshipObj{
+        RelationshipObj{
+            spdxElementId: element.to_string(),
+            relationshipType: relationship_type.to_string(),
+            relatedSpdxElement: related.to_string(),
+        }
+    }
+
+    fn packages() -> Packages{
+        Packages{
+            documentDescribes: Some(vec!["SPDXRef-app".to_string()]),
+            relationships: Some(vec![
+                relationship("SPDXRef-app", "DEPENDS_ON", "SPDXRef-lib"),
+                relationship("SPDXRef-test", "DEV_DEPENDENCY_OF", "SPDXRef-app"),
+                relationship("SPDXRef-lib", "DEPENDS_ON (build)", "SPDXRef-tool"),
+                relationship("SPDXRef-lib", "CONTAINED_BY", "SPDXRef-app"),
+                relationship("SPDXRef-app", "GENERATES", "SPDXRef-binary"),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    fn entry<'a>(graph: &'a HashMap<String, GraphEntry>, id: &str) -> (&'a str, usize, &'a str){
+        let entry = &graph[id];
+        (entry.parent.as_str(), entry.depth, entry.relationship_type.as_str())
+    }
+
+    #[test]
+    fn walks_from_the_described_root(){
+        let graph = dependency_graph(&packages());
+        assert_eq!(entry(&graph, "SPDXRef-app"), ("SPDXRef-DOCUMENT", 0, "DESCRIBES"));
+        // The first edge found keeps the shortest path; the later CONTAINED_BY is ignored.
+        assert_eq!(entry(&graph, "SPDXRef-lib"), ("SPDXRef-app", 1, "DEPENDS_ON"));
+    }
+
+    #[test]
+    fn flips_child_to_parent_relationships(){
+        let graph = dependency_graph(&packages());
+        assert_eq!(entry(&graph, "SPDXRef-test"), ("SPDXRef-app", 1, "DEV_DEPENDENCY_OF"));
+    }
+
+    #[test]
+    fn keeps_lifecycle_scope_suffix(){
+        let graph = dependency_graph(&packages());
+        assert_eq!(entry(&graph, "SPDXRef-tool"), ("SPDXRef-lib", 2, "DEPENDS_ON (build)"));
+    }
+
+    #[test]
+    fn leaves_out_unrelated_and_unreachable_packages(){
+        let graph = dependency_graph(&packages());
+        assert!(!graph.contains_key("SPDXRef-binary"));
+        assert!(!graph.contains_key("SPDXRef-DOCUMENT"));
+        assert_eq!(graph.len(), 4);
+    }
+
+    #[test]
+    fn converts_rdf_and_spdx3_relationship_names(){
+        assert_eq!(relationship_type_name("dependsOn"), "DEPENDS_ON");
+        assert_eq!(relationship_type_name("http://spdx.org/rdf/terms#relationshipType_devDependencyOf"), "DEV_DEPENDENCY_OF");
+    }
+}
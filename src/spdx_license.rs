@@ -1,32 +1,111 @@
+// Model field names mirror the SPDX JSON keys.
+#![allow(non_snake_case)]
+
 use serde_derive::{Deserialize, Serialize};
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
-use csv::{QuoteStyle, Writer, WriterBuilder};
-use std::error::Error; 
+use csv::{QuoteStyle, WriterBuilder};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
 use log::info;
 use regex::Regex;
+use crate::spdx3_license;
+use crate::spdx_diagnostics;
+use crate::spdx_docref;
+use crate::spdx_graph;
+use crate::spdx_rdf;
+use crate::spdx_tag_value;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct ReferenceObj{
     pub referenceCategory: String,
     pub referenceLocator: String,
     pub referenceType: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct PackageObj{
+    pub SPDXID: Option<String>,
     pub licenseDeclared: Option<String>,
     pub licenseConcluded: Option<String>,
+    pub licenseInfoFromFiles: Option<Vec<String>>,
     pub externalRefs: Option<Option<Vec<ReferenceObj>>>,
+    pub hasFiles: Option<Vec<String>>,
+    pub checksums: Option<Vec<ChecksumObj>>,
     pub name: String,
+    pub versionInfo: Option<String>,
+    pub supplier: Option<String>,
+    pub originator: Option<String>,
+    pub copyrightText: Option<String>,
+    pub downloadLocation: Option<String>,
+    pub homepage: Option<String>,
+    // Name and namespace of the related document a merged package came from.
+    #[serde(skip)]
+    pub sourceDocument: Option<(String, String)>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ChecksumObj{
+    pub algorithm: String,
+    pub checksumValue: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct FileObj{
+    pub SPDXID: String,
+    pub fileName: String,
+    pub checksums: Option<Vec<ChecksumObj>>,
+    pub licenseConcluded: Option<String>,
+    pub licenseInfoInFiles: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct PointerObj{
+    pub offset: Option<u64>,
+    pub lineNumber: Option<u64>,
+    pub reference: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RangeObj{
+    pub startPointer: PointerObj,
+    pub endPointer: PointerObj,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct SnippetObj{
+    pub SPDXID: String,
+    pub snippetFromFile: String,
+    pub name: Option<String>,
+    pub ranges: Option<Vec<RangeObj>>,
+    pub licenseConcluded: Option<String>,
+    pub licenseInfoInSnippets: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct RelationshipObj{
+    pub spdxElementId: String,
+    pub relationshipType: String,
+    pub relatedSpdxElement: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Packages{
     pub packages: Vec<PackageObj>,
+    pub files: Option<Vec<FileObj>>,
+    pub snippets: Option<Vec<SnippetObj>>,
+    pub relationships: Option<Vec<RelationshipObj>>,
+    pub documentDescribes: Option<Vec<String>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct LicenseInfo{
     pub extractedText: String,
     pub licenseId: String,
@@ -34,24 +113,146 @@ pub struct LicenseInfo{
     pub comment: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct HasLicenseInfo{
     pub hasExtractedLicensingInfos: Option<Option<Vec<LicenseInfo>>>,
+    pub externalDocumentRefs: Option<Vec<ExternalDocumentRef>>,
     pub documentNamespace: String,
     pub name: String,
+    pub spdxVersion: Option<String>,
+    pub dataLicense: Option<String>,
+    pub creationInfo: Option<CreationInfo>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct CreationInfo{
+    pub created: Option<String>,
+    pub creators: Option<Vec<String>>,
+    pub licenseListVersion: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct ExternalDocumentRef{
+    pub externalDocumentId: String,
+    pub spdxDocument: String,
+    pub checksum: Option<ChecksumObj>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LicenseHeader{
     name: String,
     namespace: String,
     group: String,
     version: String,
+    #[serde(rename = "package name")]
+    package_name: String,
+    #[serde(rename = "package group")]
+    package_group: String,
+    #[serde(rename = "package version")]
+    package_version: String,
     #[serde(rename = "package reference")]
     package_reference: String,
+    #[serde(rename = "package identifier")]
+    package_identifier: String,
+    #[serde(rename = "identifier type")]
+    identifier_type: String,
     license: String,
-    #[serde(rename = "alternate package reference")]
-    alternate_ref: String,
+    #[serde(rename = "license type")]
+    license_type: String,
+    #[serde(rename = "license status")]
+    license_status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supplier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    originator: Option<String>,
+    #[serde(rename = "copyright text", skip_serializing_if = "Option::is_none")]
+    copyright_text: Option<String>,
+    #[serde(rename = "download location", skip_serializing_if = "Option::is_none")]
+    download_location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<String>,
+    #[serde(rename = "cpe22Type")]
+    cpe22: String,
+    #[serde(rename = "cpe23Type")]
+    cpe23: String,
+    #[serde(rename = "maven-central")]
+    maven_central: String,
+    npm: String,
+    swh: String,
+    gitoid: String,
+    #[serde(rename = "other references")]
+    other_refs: String,
+    #[serde(rename = "direct parent")]
+    direct_parent: String,
+    depth: String,
+    #[serde(rename = "relationship type")]
+    relationship_type: String,
+    #[serde(rename = "resolved license", skip_serializing_if = "Option::is_none")]
+    resolved_license: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MetadataHeader{
+    #[serde(rename = "sbom file")]
+    sbom_file: String,
+    name: String,
+    namespace: String,
+    #[serde(rename = "spdxVersion")]
+    spdx_version: String,
+    #[serde(rename = "dataLicense")]
+    data_license: String,
+    created: String,
+    tools: String,
+    organizations: String,
+    persons: String,
+    #[serde(rename = "licenseListVersion")]
+    license_list_version: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileLicenseHeader{
+    #[serde(rename = "file name")]
+    file_name: String,
+    #[serde(rename = "file SPDXID")]
+    file_spdx_id: String,
+    checksum: String,
+    #[serde(rename = "package name")]
+    package_name: String,
+    #[serde(rename = "package reference")]
+    package_reference: String,
+    #[serde(rename = "license concluded")]
+    license_concluded: String,
+    #[serde(rename = "license info in file")]
+    license_info_in_file: String,
+    #[serde(rename = "package license info from files")]
+    package_license_info_from_files: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SnippetLicenseHeader{
+    #[serde(rename = "snippet SPDXID")]
+    snippet_spdx_id: String,
+    #[serde(rename = "snippet name")]
+    snippet_name: String,
+    #[serde(rename = "file name")]
+    file_name: String,
+    #[serde(rename = "byte range")]
+    byte_range: String,
+    #[serde(rename = "line range")]
+    line_range: String,
+    #[serde(rename = "package name")]
+    package_name: String,
+    #[serde(rename = "package reference")]
+    package_reference: String,
+    #[serde(rename = "package license declared")]
+    package_license_declared: String,
+    #[serde(rename = "license concluded")]
+    license_concluded: String,
+    #[serde(rename = "license info in snippet")]
+    license_info_in_snippet: String,
+    #[serde(rename = "not in package license")]
+    not_in_package_license: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -64,149 +265,545 @@ pub struct LicenseRefHeader{
     comment: String,
 }
 
-pub async fn get_spdx_bom_license(filepath: &str, output_path: &String, ref_file_path: &String){
+/// SPDX YAML mirrors the JSON structure, so it is only told apart by extension.
+fn is_yaml(filepath: &str) -> bool{
+    filepath.ends_with(".yaml") || filepath.ends_with(".yml")
+}
+
+/// Optional SPDX outputs, on top of the license and license ref CSVs.
+#[derive(Debug, Default)]
+pub struct SpdxExportOptions{
+    pub file_csv_path: Option<String>,
+    pub snippet_csv_path: Option<String>,
+    pub suppress_noassertion: bool,
+    pub resolve_license_refs: bool,
+    pub attribution_columns: bool,
+    pub related_docs_path: Option<String>,
+    pub metadata_path: Option<String>,
+    pub diagnostics_path: Option<String>,
+}
+
+/// Parses an SPDX document in any supported form: JSON, YAML, tag-value, RDF/XML or 3.0 JSON-LD.
+pub fn parse_spdx_document(filepath: &str, content_str: &str) -> Result<(Packages, HasLicenseInfo, Vec<spdx_diagnostics::Diagnostic>), Box<dyn Error>>{
+    if is_yaml(filepath) {
+        let document: serde_json::Value = serde_yaml::from_str(content_str).map_err(|e| format!("Error converting Yaml: {}", e))?;
+        spdx_diagnostics::parse_lenient(&document).map_err(|e| format!("Error converting Yaml: {}", e).into())
+    } else if spdx_tag_value::is_tag_value(filepath, content_str) {
+        let (data, license_extract) = spdx_tag_value::parse_tag_value(content_str);
+        Ok((data, license_extract, Vec::new()))
+    } else if spdx_rdf::is_rdf(filepath, content_str) {
+        let (data, license_extract) = spdx_rdf::parse_rdf(content_str).map_err(|e| format!("Error converting RDF/XML: {}", e))?;
+        Ok((data, license_extract, Vec::new()))
+    } else if spdx3_license::is_spdx3(content_str) {
+        let (data, license_extract) = spdx3_license::parse_spdx3(content_str).map_err(|e| format!("Error converting SPDX 3.0 JSON-LD: {}", e))?;
+        Ok((data, license_extract, Vec::new()))
+    } else {
+        let document: serde_json::Value = serde_json::from_str(content_str).map_err(|e| format!("Error converting Json: {}", e))?;
+        spdx_diagnostics::parse_lenient(&document).map_err(|e| format!("Error converting Json: {}", e).into())
+    }
+}
+
+pub async fn get_spdx_bom_license(filepath: &str, output_path: &String, ref_file_path: &String, options: &SpdxExportOptions){
     let mut file = File::open(filepath).await.expect("Error reading the file, make sure the path exists");
     let mut content_str = String::new();
     file.read_to_string(& mut content_str).await.expect("Error Reading file to variable");
-    let data: Packages = serde_json::from_str(&content_str).expect("Error converting Json");
-    let license_extract: HasLicenseInfo = serde_json::from_str(&content_str).expect("Error converting Json");
-    //let _ = write_spdx_csv(&data, &license_extract, output_path).await;
-    let _ = write_simple_spdx_csv(&data, &license_extract, output_path).await;
+    let (mut data, mut license_extract, diagnostics) = parse_spdx_document(filepath, &content_str).expect("Error parsing SPDX document");
+    spdx_diagnostics::log_diagnostics(filepath, &diagnostics);
+    if let Some(diagnostics_path) = &options.diagnostics_path {
+        let _ = spdx_diagnostics::write_diagnostics_csv(&diagnostics, diagnostics_path).await;
+    }
+    // Written before related documents are merged, so it describes this SBOM only.
+    if let Some(metadata_path) = &options.metadata_path {
+        let _ = write_metadata_csv(&license_extract, filepath, metadata_path).await;
+    }
+    if let Some(related_docs_path) = &options.related_docs_path {
+        spdx_docref::merge_related_documents(&mut data, &mut license_extract, related_docs_path);
+    }
+    let _ = write_simple_spdx_csv(&data, &license_extract, output_path, options).await;
     let _ = write_ref_csv(&license_extract, ref_file_path).await;
+    if let Some(file_csv_path) = &options.file_csv_path {
+        let _ = write_file_csv(&data, file_csv_path).await;
+    }
+    if let Some(snippet_csv_path) = &options.snippet_csv_path {
+        let _ = write_snippet_csv(&data, snippet_csv_path).await;
+    }
 }
 
-pub async fn write_ref_csv(licenseRef: &HasLicenseInfo, ref_file_path: &String) -> Result<(), Box<dyn Error>>{
+/// Writes the document's SPDX version, data license and creation info, with
+/// creators split into tools, organizations and persons.
+pub async fn write_metadata_csv(license_extract: &HasLicenseInfo, sbom_file: &str, metadata_path: &String) -> Result<(), Box<dyn Error>>{
+    let mut wtr = WriterBuilder::new()
+        .delimiter(b'\t')
+        .quote_style(QuoteStyle::Always)
+        .has_headers(false)
+        .from_path(metadata_path)?;
+
+    wtr.write_record(["sbom file", "name", "namespace", "spdxVersion", "dataLicense", "created", "tools", "organizations", "persons", "licenseListVersion"])?;
+
+    let creation_info = license_extract.creationInfo.as_ref();
+    let creators = |prefix: &str| -> String {
+        creation_info.and_then(|c| c.creators.as_ref()).into_iter().flatten()
+            .filter_map(|creator| creator.strip_prefix(prefix))
+            .map(|creator| creator.trim())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    wtr.serialize(MetadataHeader{
+        sbom_file: sbom_file.to_string(),
+        name: license_extract.name.to_string(),
+        namespace: license_extract.documentNamespace.to_string(),
+        spdx_version: license_extract.spdxVersion.clone().unwrap_or_default(),
+        data_license: license_extract.dataLicense.clone().unwrap_or_default(),
+        created: creation_info.and_then(|c| c.created.clone()).unwrap_or_default(),
+        tools: creators("Tool:"),
+        organizations: creators("Organization:"),
+        persons: creators("Person:"),
+        license_list_version: creation_info.and_then(|c| c.licenseListVersion.clone()).unwrap_or_default(),
+    })?;
+    wtr.flush()?;
+    Ok(())
+}
+
+pub async fn write_ref_csv(license_ref: &HasLicenseInfo, ref_file_path: &String) -> Result<(), Box<dyn Error>>{
     let mut wrt_ref = WriterBuilder::new()
         .delimiter(b'\t')
         .quote_style(QuoteStyle::Always)
+        .has_headers(false)
         .from_path(ref_file_path)?;
 
-    wrt_ref.write_record(&["licenseId", "name", "extracted text", "comment"])?;
-
-    if let Some(inner_license_map) = &licenseRef.hasExtractedLicensingInfos{
-        if let Some(license_map) = inner_license_map{
-            for license_info in license_map{
-                wrt_ref.write_record(&[
-                    &license_info.licenseId,
-                    &license_info.name,
-                    &license_info.extractedText,
-                    &license_info.comment,
-                ])?;
-            }
+    wrt_ref.write_record(["licenseId", "name", "extracted text", "comment"])?;
+
+    if let Some(Some(license_map)) = &license_ref.hasExtractedLicensingInfos{
+        for license_info in license_map{
+            wrt_ref.serialize(LicenseRefHeader{
+                license_id: license_info.licenseId.to_string(),
+                name: license_info.name.to_string(),
+                extracted_text: license_info.extractedText.to_string(),
+                comment: license_info.comment.to_string(),
+            })?;
         }
     }
     wrt_ref.flush()?;
     Ok(())
 }
 
-pub async fn write_simple_spdx_csv(packages: &Packages, license_extract: &HasLicenseInfo, csv_path: &String) -> Result<(), Box<dyn Error>>{
+fn package_purl(package: &PackageObj) -> String{
+    if let Some(Some(external_refs)) = &package.externalRefs{
+        for reference in external_refs{
+            if reference.referenceType == "purl"{
+                return reference.referenceLocator.to_string();
+            }
+        }
+    }
+    "".to_string()
+}
+
+/// Identifies a package by the first of: purl, CPE, SPDXID with name and
+/// version, checksum. Returns the identifier and which kind it is.
+fn package_identity(package: &PackageObj, purl: &str) -> (String, &'static str){
+    if !purl.is_empty() {
+        return (purl.to_string(), "purl");
+    }
+    if let Some(Some(external_refs)) = &package.externalRefs {
+        for cpe_type in ["cpe23Type", "cpe22Type"]{
+            if let Some(reference) = external_refs.iter().find(|r| r.referenceType == cpe_type) {
+                return (reference.referenceLocator.to_string(), "cpe");
+            }
+        }
+    }
+    if let Some(spdx_id) = &package.SPDXID {
+        let identifier = match &package.versionInfo {
+            Some(version) => format!("{} {}@{}", spdx_id, package.name, version),
+            None => format!("{} {}", spdx_id, package.name),
+        };
+        return (identifier, "spdxid");
+    }
+    let checksum = strongest_checksum(&package.checksums);
+    if !checksum.is_empty() {
+        return (checksum, "checksum");
+    }
+    ("".to_string(), "none")
+}
+
+/// Picks the strongest checksum of a file or package, formatted as `ALGORITHM:value`.
+fn strongest_checksum(checksums: &Option<Vec<ChecksumObj>>) -> String{
+    let Some(checksums) = checksums else {
+        return "".to_string();
+    };
+    ["SHA256", "SHA1", "MD5"].iter()
+        .find_map(|algorithm| checksums.iter().find(|c| c.algorithm == *algorithm))
+        .or(checksums.first())
+        .map(|c| format!("{}:{}", c.algorithm, c.checksumValue))
+        .unwrap_or_default()
+}
+
+/// Maps each file SPDXID to the packages containing it, from the package
+/// `hasFiles` list and from `CONTAINS`/`CONTAINED_BY` relationships.
+fn file_owners(packages: &Packages) -> HashMap<&str, Vec<&PackageObj>>{
+    let mut owners: HashMap<&str, Vec<&PackageObj>> = HashMap::new();
+    let package_by_id: HashMap<&str, &PackageObj> = packages.packages.iter()
+        .filter_map(|p| p.SPDXID.as_deref().map(|id| (id, p)))
+        .collect();
+    for package in &packages.packages{
+        for file_id in package.hasFiles.iter().flatten(){
+            owners.entry(file_id).or_default().push(package);
+        }
+    }
+    for relationship in packages.relationships.iter().flatten(){
+        let (package_id, file_id) = match relationship.relationshipType.as_str() {
+            "CONTAINS" => (&relationship.spdxElementId, &relationship.relatedSpdxElement),
+            "CONTAINED_BY" => (&relationship.relatedSpdxElement, &relationship.spdxElementId),
+            _ => continue,
+        };
+        if let Some(package) = package_by_id.get(package_id.as_str()){
+            let file_owners = owners.entry(file_id).or_default();
+            if !file_owners.iter().any(|p| std::ptr::eq(*p, *package)){
+                file_owners.push(package);
+            }
+        }
+    }
+    owners
+}
+
+/// Writes one row per file and owning package; files without an owner are
+/// written with empty package columns.
+pub async fn write_file_csv(packages: &Packages, file_csv_path: &String) -> Result<(), Box<dyn Error>>{
+    let mut wtr = WriterBuilder::new()
+        .delimiter(b'\t')
+        .quote_style(QuoteStyle::Always)
+        .has_headers(false)
+        .from_path(file_csv_path)?;
+
+    wtr.write_record(["file name", "file SPDXID", "checksum", "package name", "package reference", "license concluded", "license info in file", "package license info from files"])?;
+
+    let owners = file_owners(packages);
+    for file in packages.files.iter().flatten(){
+        let file_owners = owners.get(file.SPDXID.as_str()).cloned().unwrap_or_default();
+        let owner_list: Vec<Option<&PackageObj>> = if file_owners.is_empty() {
+            vec![None]
+        } else {
+            file_owners.into_iter().map(Some).collect()
+        };
+        for owner in owner_list{
+            wtr.serialize(FileLicenseHeader{
+                file_name: file.fileName.to_string(),
+                file_spdx_id: file.SPDXID.to_string(),
+                checksum: strongest_checksum(&file.checksums),
+                package_name: owner.map(|p| p.name.to_string()).unwrap_or_default(),
+                package_reference: owner.map(package_purl).unwrap_or_default(),
+                license_concluded: file.licenseConcluded.clone().unwrap_or_default(),
+                license_info_in_file: file.licenseInfoInFiles.clone().unwrap_or_default().join(", "),
+                package_license_info_from_files: owner.and_then(|p| p.licenseInfoFromFiles.clone()).unwrap_or_default().join(", "),
+            })?;
+        }
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Individual license identifiers of an SPDX expression, without operators,
+/// parentheses, NOASSERTION or NONE.
+pub fn license_ids(expression: &str) -> Vec<String>{
+    expression.split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|token| !token.is_empty())
+        .filter(|token| !matches!(*token, "AND" | "OR" | "WITH" | "and" | "or" | "with" | "NOASSERTION" | "NONE"))
+        .map(|token| token.to_string())
+        .collect()
+}
+
+fn snippet_ranges(snippet: &SnippetObj) -> (String, String){
+    let mut byte_ranges = Vec::new();
+    let mut line_ranges = Vec::new();
+    for range in snippet.ranges.iter().flatten(){
+        if let (Some(start), Some(end)) = (range.startPointer.offset, range.endPointer.offset){
+            byte_ranges.push(format!("{}:{}", start, end));
+        }
+        if let (Some(start), Some(end)) = (range.startPointer.lineNumber, range.endPointer.lineNumber){
+            line_ranges.push(format!("{}:{}", start, end));
+        }
+    }
+    (byte_ranges.join(", "), line_ranges.join(", "))
+}
+
+/// Writes one row per snippet and owning package. A snippet is flagged when
+/// one of its licenses is missing from the package's declared expression;
+/// the concluded license is used, or `licenseInfoInSnippets` when it is not asserted.
+pub async fn write_snippet_csv(packages: &Packages, snippet_csv_path: &String) -> Result<(), Box<dyn Error>>{
     let mut wtr = WriterBuilder::new()
         .delimiter(b'\t')
         .quote_style(QuoteStyle::Always)
         .has_headers(false)
-        .from_path(csv_path)?;
+        .from_path(snippet_csv_path)?;
 
-    wtr.write_record(&["name", "namespace", "group", "version", "package reference", "license", "alternate package reference"])?;
+    wtr.write_record(["snippet SPDXID", "snippet name", "file name", "byte range", "line range", "package name", "package reference", "package license declared", "license concluded", "license info in snippet", "not in package license"])?;
 
-    for package in &packages.packages{
-        let mut purl = "";
-        let mut license_declared = "";
-        let mut license_concluded = "";
-        let mut alternate_ref = Vec::new();
-        if let Some(license_expression) = &package.licenseDeclared{
-            license_declared = license_expression;
-        }
-        if let Some(license_expression) = &package.licenseConcluded{
-            license_concluded = license_expression;
-        }
-        if let Some(inner_external_ref) = &package.externalRefs{
-            if let Some(external_refs) = inner_external_ref{
-                for reference in external_refs{
-                    if &reference.referenceType == "purl"{
-                        purl = &reference.referenceLocator;
-                    }else{
-                        alternate_ref.push(reference.referenceLocator.clone());
-                    }
-                }
-            }
+    let owners = file_owners(packages);
+    let file_names: HashMap<&str, &str> = packages.files.iter().flatten()
+        .map(|f| (f.SPDXID.as_str(), f.fileName.as_str()))
+        .collect();
+    let mut flagged = 0;
+    for snippet in packages.snippets.iter().flatten(){
+        let (byte_range, line_range) = snippet_ranges(snippet);
+        let license_concluded = snippet.licenseConcluded.clone().unwrap_or_default();
+        let license_info = snippet.licenseInfoInSnippets.clone().unwrap_or_default();
+        let mut snippet_licenses = license_ids(&license_concluded);
+        if snippet_licenses.is_empty() {
+            snippet_licenses = license_info.iter().flat_map(|l| license_ids(l)).collect();
         }
-        let alternate_ref_str = alternate_ref.join("\n");
-        if !license_declared.is_empty() {
-            wtr.serialize(LicenseHeader{
-                name: license_extract.name.to_string(),
-                namespace: license_extract.documentNamespace.to_string(),
-                group: "".to_string(),
-                version: "".to_string(),
-                package_reference: purl.to_string(),
-                license: license_declared.to_string(),
-                alternate_ref: alternate_ref_str.clone(),
-            });
-        }
-        if !license_concluded.is_empty() {
-            wtr.serialize(LicenseHeader{
-                name: license_extract.name.to_string(),
-                namespace: license_extract.documentNamespace.to_string(),
-                group: "".to_string(),
-                version: "".to_string(),
-                package_reference: purl.to_string(),
-                license: license_concluded.to_string(),
-                alternate_ref: alternate_ref_str,
-            });
+        let snippet_owners = owners.get(snippet.snippetFromFile.as_str()).cloned().unwrap_or_default();
+        let owner_list: Vec<Option<&PackageObj>> = if snippet_owners.is_empty() {
+            vec![None]
+        } else {
+            snippet_owners.into_iter().map(Some).collect()
+        };
+        for owner in owner_list{
+            let package_declared = owner.and_then(|p| p.licenseDeclared.clone()).unwrap_or_default();
+            let declared_ids = license_ids(&package_declared);
+            let not_in_package_license = owner.is_some() && snippet_licenses.iter().any(|l| !declared_ids.contains(l));
+            if not_in_package_license {
+                flagged += 1;
+            }
+            wtr.serialize(SnippetLicenseHeader{
+                snippet_spdx_id: snippet.SPDXID.to_string(),
+                snippet_name: snippet.name.clone().unwrap_or_default(),
+                file_name: file_names.get(snippet.snippetFromFile.as_str()).unwrap_or(&"").to_string(),
+                byte_range: byte_range.clone(),
+                line_range: line_range.clone(),
+                package_name: owner.map(|p| p.name.to_string()).unwrap_or_default(),
+                package_reference: owner.map(package_purl).unwrap_or_default(),
+                package_license_declared: package_declared,
+                license_concluded: license_concluded.clone(),
+                license_info_in_snippet: license_info.join(", "),
+                not_in_package_license,
+            })?;
         }
     }
     wtr.flush()?;
+    if flagged > 0 {
+        info!("{} snippet(s) carry a license not found in their package's declared license", flagged);
+    }
     Ok(())
 }
 
-pub async fn write_spdx_csv(packages: &Packages, licenseRef: &HasLicenseInfo, csv_path: &String) -> Result<(), Box<dyn Error>>{
-    // This block of code is extensive
-    // It is capable of splitting the Licenses under license Declared field and remove the brackets and write it on separate rows
-    // It is capable of mapping the license ID's against hasExtractedLicensingInfos field in SBOM and update "license name" column
-    // Right now, it is not being used
-    let mut wtr = Writer::from_path(csv_path)?;
+/// External reference types exported in a column of their own, besides `purl`.
+const REFERENCE_TYPES: [&str; 6] = ["cpe22Type", "cpe23Type", "maven-central", "npm", "swh", "gitoid"];
+
+/// Returns the namespace segment of a package URL, e.g. `org.apache.commons`
+/// for `pkg:maven/org.apache.commons/commons-lang3@3.12.0`.
+pub fn purl_namespace(purl: &str) -> String{
+    let Some(rest) = purl.strip_prefix("pkg:") else {
+        return "".to_string();
+    };
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let segments: Vec<&str> = rest.split('/').collect();
+    if segments.len() < 3 {
+        return "".to_string();
+    }
+    segments[1..segments.len() - 1].join("/").replace("%40", "@")
+}
+
+// LicenseRef ids, optionally qualified with the external document they come from.
+pub const LICENSE_REF_PATTERN: &str = r"(DocumentRef-[A-Za-z0-9.\-]+:)?LicenseRef-[A-Za-z0-9.\-]+";
+
+/// Maps each `hasExtractedLicensingInfos` licenseId to its name, skipping unnamed entries.
+fn license_ref_names(license_extract: &HasLicenseInfo) -> HashMap<String, String>{
+    let mut names = HashMap::new();
+    if let Some(Some(license_map)) = &license_extract.hasExtractedLicensingInfos{
+        for license_info in license_map{
+            if !license_info.name.is_empty() && license_info.name != "NOASSERTION" {
+                names.insert(license_info.licenseId.to_string(), license_info.name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Replaces each LicenseRef in the expression with its extracted license name,
+/// keeping operators and parentheses. Refs without a name are left as they are
+/// and counted in `unresolved`.
+fn resolve_license_refs(expression: &str, license_ref_re: &Regex, names: &HashMap<String, String>, unresolved: &mut BTreeMap<String, usize>) -> String{
+    license_ref_re.replace_all(expression, |caps: &regex::Captures| {
+        let license_ref = &caps[0];
+        match names.get(license_ref) {
+            Some(name) => name.to_string(),
+            None => {
+                *unresolved.entry(license_ref.to_string()).or_default() += 1;
+                license_ref.to_string()
+            }
+        }
+    }).to_string()
+}
+
+/// Classifies a license field as `asserted`, `none` (SPDX `NONE`),
+/// `noassertion` (SPDX `NOASSERTION`) or `missing` when it is absent or empty.
+pub fn license_status(license: Option<&str>) -> &'static str{
+    match license.map(|l| l.trim()) {
+        None | Some("") => "missing",
+        Some("NOASSERTION") => "noassertion",
+        Some("NONE") => "none",
+        Some(_) => "asserted",
+    }
+}
+
+pub async fn write_simple_spdx_csv(packages: &Packages, license_extract: &HasLicenseInfo, csv_path: &String, options: &SpdxExportOptions) -> Result<(), Box<dyn Error>>{
+    let mut wtr = WriterBuilder::new()
+        .delimiter(b'\t')
+        .quote_style(QuoteStyle::Always)
+        .has_headers(false)
+        .from_path(csv_path)?;
+
+    let mut header = vec!["name", "namespace", "group", "version", "package name", "package group", "package version", "package reference", "package identifier", "identifier type", "license", "license type", "license status"];
+    if options.attribution_columns {
+        header.extend(["supplier", "originator", "copyright text", "download location", "homepage"]);
+    }
+    header.extend(["cpe22Type", "cpe23Type", "maven-central", "npm", "swh", "gitoid", "other references", "direct parent", "depth", "relationship type"]);
+    if options.resolve_license_refs {
+        header.push("resolved license");
+    }
+    wtr.write_record(&header)?;
+
+    // Attribution fields follow the same NOASSERTION handling as license rows.
+    let attribution = |value: &Option<String>| -> Option<String> {
+        if !options.attribution_columns {
+            return None;
+        }
+        match value.as_deref().map(|v| v.trim()) {
+            Some("NOASSERTION") if options.suppress_noassertion => Some("".to_string()),
+            Some(v) => Some(v.to_string()),
+            None => Some("".to_string()),
+        }
+    };
+    let license_ref_re = Regex::new(LICENSE_REF_PATTERN).unwrap();
+    let license_names = license_ref_names(license_extract);
+    let mut unresolved: BTreeMap<String, usize> = BTreeMap::new();
+
+    let graph = spdx_graph::dependency_graph(packages);
+    // Parents are shown by purl where they have one, otherwise by SPDXID.
+    let parent_refs: HashMap<&str, String> = packages.packages.iter()
+        .filter_map(|p| p.SPDXID.as_deref().map(|id| (id, package_purl(p))))
+        .filter(|(_, purl)| !purl.is_empty())
+        .collect();
+
+    let mut unasserted = Vec::new();
     for package in &packages.packages{
-        let package_name = &package.name;
-        let re = Regex::new(r" OR | AND ").unwrap();
-        if let Some(license_id_spdx) = &package.licenseDeclared{
-            let license_id_list = license_id_spdx.replace("(","").replace(")","");
-            let license_ids: Vec<&str> = re.split(&license_id_list).collect();
-            for id in license_ids{
-                let mut license_name = id;
-                if let Some(license_map) = &licenseRef.hasExtractedLicensingInfos{
-                    if let Some(mapping) = license_map{
-                        for map in mapping{
-                            if map.licenseId == license_name{
-                                license_name = &map.name;
-                                break;
-                            }
-                        }
-                    }
+        let external_refs = match &package.externalRefs {
+            Some(Some(external_refs)) => external_refs.as_slice(),
+            _ => &[],
+        };
+        let purls: Vec<&str> = external_refs.iter()
+            .filter(|r| r.referenceType == "purl")
+            .map(|r| r.referenceLocator.as_str())
+            .collect();
+        let references = |reference_type: &str| -> String {
+            external_refs.iter()
+                .filter(|r| r.referenceType == reference_type)
+                .map(|r| r.referenceLocator.as_str())
+                .collect::<Vec<_>>()
+                .join("; ")
+        };
+        // Types without a column of their own keep their category, as in tag-value `ExternalRef`.
+        let other_refs = external_refs.iter()
+            .filter(|r| r.referenceType != "purl" && !REFERENCE_TYPES.contains(&r.referenceType.as_str()))
+            .map(|r| format!("{} {} {}", r.referenceCategory, r.referenceType, r.referenceLocator))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let graph_entry = package.SPDXID.as_ref().and_then(|id| graph.get(id));
+        let row = LicenseHeader{
+            name: package.sourceDocument.as_ref().map(|d| &d.0).unwrap_or(&license_extract.name).to_string(),
+            namespace: package.sourceDocument.as_ref().map(|d| &d.1).unwrap_or(&license_extract.documentNamespace).to_string(),
+            group: "".to_string(),
+            version: "".to_string(),
+            package_name: package.name.to_string(),
+            package_group: "".to_string(),
+            package_version: package.versionInfo.clone().unwrap_or_default(),
+            package_reference: "".to_string(),
+            package_identifier: "".to_string(),
+            identifier_type: "".to_string(),
+            license: "".to_string(),
+            license_type: "".to_string(),
+            license_status: "".to_string(),
+            supplier: attribution(&package.supplier),
+            originator: attribution(&package.originator),
+            copyright_text: attribution(&package.copyrightText),
+            download_location: attribution(&package.downloadLocation),
+            homepage: attribution(&package.homepage),
+            cpe22: references("cpe22Type"),
+            cpe23: references("cpe23Type"),
+            maven_central: references("maven-central"),
+            npm: references("npm"),
+            swh: references("swh"),
+            gitoid: references("gitoid"),
+            other_refs,
+            direct_parent: graph_entry
+                .map(|e| parent_refs.get(e.parent.as_str()).unwrap_or(&e.parent).to_string())
+                .unwrap_or_default(),
+            depth: graph_entry.map(|e| e.depth.to_string()).unwrap_or_default(),
+            relationship_type: graph_entry.map(|e| e.relationship_type.to_string()).unwrap_or_default(),
+            resolved_license: None,
+        };
+        let declared_status = license_status(package.licenseDeclared.as_deref());
+        let concluded_status = license_status(package.licenseConcluded.as_deref());
+        if declared_status != "asserted" && concluded_status != "asserted" {
+            unasserted.push(format!("{} {}", package.name, package.versionInfo.as_deref().unwrap_or("")).trim().to_string());
+        }
+        // Packages with several purls get their rows once per purl; rows for
+        // every purl after the first are marked `additional purl`.
+        let purl_rows: Vec<LicenseHeader> = if purls.is_empty() { vec![""] } else { purls }.iter()
+            .enumerate()
+            .map(|(i, purl)| {
+                let (package_identifier, identifier_type) = package_identity(package, purl);
+                LicenseHeader{
+                    package_group: purl_namespace(purl),
+                    package_reference: purl.to_string(),
+                    package_identifier,
+                    identifier_type: if i == 0 { identifier_type } else { "additional purl" }.to_string(),
+                    ..row.clone()
                 }
-                if let Some(inner_external_ref) = &package.externalRefs{
-                    if let Some(external_refs) = inner_external_ref{
-                        let mut purl = "";
-                        let mut alternate_ref = Vec::new();
-                        for reference in external_refs{
-                            if &reference.referenceType == "purl"{
-                                purl = &reference.referenceLocator;
-                            }else{
-                                alternate_ref.push(reference.referenceLocator.clone());
-                            }
-                        }
-                        wtr.serialize(LicenseHeader{
-                            name: package_name.to_string(),
-                            namespace: licenseRef.documentNamespace.to_string(),
-                            group: "".to_string(),
-                            version: "".to_string(),
-                            package_reference: purl.to_string(),
-                            license: id.to_string(),
-                            alternate_ref: alternate_ref.join("\n").to_string(),
-                        });
-                    }
+            })
+            .collect();
+        for row in purl_rows{
+            // A package with neither field still gets a row, so it does not vanish from the export.
+            if declared_status == "missing" && concluded_status == "missing" {
+                wtr.serialize(LicenseHeader{
+                    license_status: "missing".to_string(),
+                    resolved_license: options.resolve_license_refs.then(String::new),
+                    ..row
+                })?;
+                continue;
+            }
+            for (license, license_type, status) in [(&package.licenseDeclared, "declared", declared_status), (&package.licenseConcluded, "concluded", concluded_status)]{
+                if status == "missing" || (status == "noassertion" && options.suppress_noassertion) {
+                    continue;
                 }
+                let license = license.as_deref().unwrap_or("");
+                let resolved_license = options.resolve_license_refs
+                    .then(|| resolve_license_refs(license, &license_ref_re, &license_names, &mut unresolved));
+                wtr.serialize(LicenseHeader{
+                    license: license.to_string(),
+                    license_type: license_type.to_string(),
+                    license_status: status.to_string(),
+                    resolved_license,
+                    ..row.clone()
+                })?;
             }
         }
     }
     wtr.flush()?;
+    if !unasserted.is_empty() {
+        info!("{} package(s) have no asserted license (NONE, NOASSERTION or missing):", unasserted.len());
+        for (i, package) in unasserted.iter().enumerate(){
+            if i >= 10 {
+                info!("  ... and {} more", unasserted.len() - 10);
+                break;
+            }
+            info!("  {}", package);
+        }
+    }
+    if !unresolved.is_empty() {
+        info!("{} LicenseRef(s) could not be resolved from hasExtractedLicensingInfos:", unresolved.len());
+        for (license_ref, count) in &unresolved{
+            info!("  {} ({} row(s))", license_ref, count);
+        }
+    }
     Ok(())
 }
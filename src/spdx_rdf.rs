@@ -0,0 +1,436 @@
+use crate::spdx_graph::relationship_type_name;
+use crate::spdx_license::{ChecksumObj, CreationInfo, ExternalDocumentRef, HasLicenseInfo, LicenseInfo, PackageObj, Packages, ReferenceObj, RelationshipObj};
+use roxmltree::{Document, Node};
+use std::collections::HashSet;
+use std::error::Error;
+
+const SPDX_NS: &str = "http://spdx.org/rdf/terms#";
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const RDFS_NS: &str = "http://www.w3.org/2000/01/rdf-schema#";
+const DOAP_NS: &str = "http://usefulinc.com/ns/doap#";
+
+/// Sniffs RDF by the `.rdf`/`.rdf.xml` extension or a leading `<`.
+pub fn is_rdf(filepath: &str, content: &str) -> bool{
+    if filepath.ends_with(".rdf") || filepath.ends_with(".rdf.xml") {
+        return true;
+    }
+    content.trim_start().starts_with('<')
+}
+
+fn is_spdx(node: &Node, name: &str) -> bool{
+    node.is_element() && node.tag_name().name() == name && node.tag_name().namespace() == Some(SPDX_NS)
+}
+
+fn child<'a, 'input>(node: &Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>>{
+    node.children().find(|c| is_spdx(c, name))
+}
+
+fn child_text(node: &Node, name: &str) -> Option<String>{
+    child(node, name).and_then(|c| c.text()).map(|t| t.trim().to_string())
+}
+
+fn comment_text(node: &Node) -> Option<String>{
+    node.children()
+        .find(|c| c.is_element() && c.tag_name().name() == "comment" && c.tag_name().namespace() == Some(RDFS_NS))
+        .and_then(|c| c.text())
+        .map(|t| t.trim().to_string())
+}
+
+/// Reads a literal property, mapping `#noassertion`/`#none` resources to their keywords.
+fn literal_or_keyword(node: &Node, name: &str) -> Option<String>{
+    let property = child(node, name)?;
+    match resource(&property) {
+        Some(uri) => Some(uri_license_id(&uri)),
+        None => property.text().map(|t| t.trim().to_string()),
+    }
+}
+
+fn resource(node: &Node) -> Option<String>{
+    node.attribute((RDF_NS, "resource")).map(|r| r.to_string())
+}
+
+fn about(node: &Node) -> Option<String>{
+    node.attribute((RDF_NS, "about")).map(|r| r.to_string())
+}
+
+/// Maps a license URI to its SPDX identifier, e.g.
+/// `http://spdx.org/licenses/MIT` to `MIT` and `...#LicenseRef-1` to `LicenseRef-1`.
+fn uri_license_id(uri: &str) -> String{
+    if uri.ends_with("#noassertion") {
+        return "NOASSERTION".to_string();
+    }
+    if uri.ends_with("#none") {
+        return "NONE".to_string();
+    }
+    match uri.rsplit_once('#').or_else(|| uri.rsplit_once('/')) {
+        Some((_, id)) => id.to_string(),
+        None => uri.to_string(),
+    }
+}
+
+/// Maps `...#referenceCategory_packageManager` to the JSON form `PACKAGE-MANAGER`.
+fn reference_category(uri: &str) -> String{
+    let category = uri.rsplit('_').next().unwrap_or("");
+    let mut json_category = String::new();
+    for c in category.chars() {
+        if c.is_uppercase() {
+            json_category.push('-');
+        }
+        json_category.push(c.to_ascii_uppercase());
+    }
+    json_category
+}
+
+/// Builds a license expression from a property such as `spdx:licenseDeclared`
+/// or `spdx:member`, which either points at a license with `rdf:resource` or
+/// wraps a license node.
+fn property_expression(property: &Node) -> String{
+    if let Some(uri) = resource(property) {
+        return uri_license_id(&uri);
+    }
+    match property.children().find(|c| c.is_element()) {
+        Some(license) => license_expression(&license),
+        None => property.text().unwrap_or("").trim().to_string(),
+    }
+}
+
+fn license_expression(license: &Node) -> String{
+    match license.tag_name().name() {
+        "ConjunctiveLicenseSet" | "DisjunctiveLicenseSet" => {
+            let operator = if license.tag_name().name() == "ConjunctiveLicenseSet" { " AND " } else { " OR " };
+            let members: Vec<String> = license.children()
+                .filter(|c| is_spdx(c, "member"))
+                .map(|member| {
+                    let expression = property_expression(&member);
+                    let is_set = member.children().any(|c| is_spdx(&c, "ConjunctiveLicenseSet") || is_spdx(&c, "DisjunctiveLicenseSet"));
+                    if is_set {
+                        format!("({})", expression)
+                    } else {
+                        expression
+                    }
+                })
+                .collect();
+            members.join(operator)
+        }
+        "WithExceptionOperator" => {
+            let member = child(license, "member").map(|m| property_expression(&m)).unwrap_or_default();
+            let exception = child(license, "licenseException")
+                .and_then(|e| {
+                    e.children()
+                        .find(|c| c.is_element())
+                        .and_then(|le| child_text(&le, "licenseExceptionId"))
+                        .or_else(|| resource(&e).map(|uri| uri_license_id(&uri)))
+                })
+                .unwrap_or_default();
+            format!("{} WITH {}", member, exception)
+        }
+        "OrLaterOperator" => {
+            let member = child(license, "member").map(|m| property_expression(&m)).unwrap_or_default();
+            format!("{}+", member)
+        }
+        _ => child_text(license, "licenseId")
+            .or_else(|| about(license).map(|uri| uri_license_id(&uri)))
+            .unwrap_or_default(),
+    }
+}
+
+/// Reads an `spdx:checksum` property, mapping the algorithm to the JSON
+/// name, e.g. `checksumAlgorithm_sha1` to `SHA1` and `checksumAlgorithm_sha3_256`
+/// to `SHA3-256`.
+fn read_checksum(property: &Node) -> Option<ChecksumObj>{
+    let checksum = property.children().find(|n| is_spdx(n, "Checksum"))?;
+    Some(ChecksumObj{
+        algorithm: child(&checksum, "algorithm")
+            .and_then(|a| resource(&a))
+            .map(|a| {
+                let name = a.rsplit('#').next().unwrap_or("");
+                name.strip_prefix("checksumAlgorithm_").unwrap_or(name).replace('_', "-").to_uppercase()
+            })
+            .unwrap_or_default(),
+        checksumValue: child_text(&checksum, "checksumValue").unwrap_or_default(),
+    })
+}
+
+fn read_package(package: &Node) -> PackageObj{
+    let mut package_obj = PackageObj{
+        SPDXID: about(package).map(|uri| uri_license_id(&uri)),
+        name: child_text(package, "name").unwrap_or_default(),
+        versionInfo: child_text(package, "versionInfo"),
+        licenseDeclared: child(package, "licenseDeclared").map(|l| property_expression(&l)),
+        licenseConcluded: child(package, "licenseConcluded").map(|l| property_expression(&l)),
+        supplier: literal_or_keyword(package, "supplier"),
+        originator: literal_or_keyword(package, "originator"),
+        copyrightText: literal_or_keyword(package, "copyrightText"),
+        downloadLocation: literal_or_keyword(package, "downloadLocation"),
+        homepage: package.children()
+            .find(|c| c.is_element() && c.tag_name().name() == "homepage" && c.tag_name().namespace() == Some(DOAP_NS))
+            .and_then(|c| c.text())
+            .map(|t| t.trim().to_string()),
+        ..Default::default()
+    };
+    let references: Vec<ReferenceObj> = package.children()
+        .filter(|c| is_spdx(c, "externalRef"))
+        .filter_map(|r| r.children().find(|c| is_spdx(c, "ExternalRef")))
+        .map(|external_ref| ReferenceObj{
+            referenceCategory: child(&external_ref, "referenceCategory")
+                .and_then(|c| resource(&c).or_else(|| c.text().map(|t| t.trim().to_string())))
+                .map(|category| reference_category(&category))
+                .unwrap_or_default(),
+            referenceType: child(&external_ref, "referenceType")
+                .and_then(|t| resource(&t).or_else(|| t.text().map(|t| t.trim().to_string())))
+                .map(|reference_type| uri_license_id(&reference_type))
+                .unwrap_or_default(),
+            referenceLocator: child_text(&external_ref, "referenceLocator").unwrap_or_default(),
+        })
+        .collect();
+    if !references.is_empty() {
+        package_obj.externalRefs = Some(Some(references));
+    }
+    let checksums: Vec<ChecksumObj> = package.children()
+        .filter(|c| is_spdx(c, "checksum"))
+        .filter_map(|c| read_checksum(&c))
+        .collect();
+    if !checksums.is_empty() {
+        package_obj.checksums = Some(checksums);
+    }
+    package_obj
+}
+
+/// Parses an SPDX 2.x RDF/XML document. Packages and extracted licenses may
+/// be nested anywhere in the graph, so they are collected from all descendants.
+pub fn parse_rdf(content: &str) -> Result<(Packages, HasLicenseInfo), Box<dyn Error>>{
+    let doc = Document::parse(content)?;
+    let mut packages = Packages::default();
+    let mut license_extract = HasLicenseInfo::default();
+
+    if let Some(document) = doc.descendants().find(|n| is_spdx(n, "SpdxDocument")) {
+        license_extract.name = child_text(&document, "name").unwrap_or_default();
+        license_extract.spdxVersion = child_text(&document, "specVersion");
+        license_extract.dataLicense = literal_or_keyword(&document, "dataLicense");
+        if let Some(creation_info) = child(&document, "creationInfo").and_then(|c| c.children().find(|n| is_spdx(n, "CreationInfo"))) {
+            let creators: Vec<String> = creation_info.children()
+                .filter(|c| is_spdx(c, "creator"))
+                .filter_map(|c| c.text().map(|t| t.trim().to_string()))
+                .collect();
+            license_extract.creationInfo = Some(CreationInfo{
+                created: child_text(&creation_info, "created"),
+                creators: if creators.is_empty() { None } else { Some(creators) },
+                licenseListVersion: child_text(&creation_info, "licenseListVersion"),
+            });
+        }
+        if let Some(uri) = about(&document) {
+            license_extract.documentNamespace = uri.split('#').next().unwrap_or("").to_string();
+        }
+        for reference in document.children().filter(|c| is_spdx(c, "externalDocumentRef")) {
+            let Some(reference) = reference.children().find(|c| is_spdx(c, "ExternalDocumentRef")) else {
+                continue;
+            };
+            let checksum = child(&reference, "checksum").and_then(|c| read_checksum(&c));
+            license_extract.externalDocumentRefs.get_or_insert(Vec::new()).push(ExternalDocumentRef{
+                externalDocumentId: child_text(&reference, "externalDocumentId").unwrap_or_default(),
+                spdxDocument: child(&reference, "spdxDocument")
+                    .and_then(|d| resource(&d).or_else(|| d.children().find(|c| c.is_element()).and_then(|c| about(&c))))
+                    .map(|d| d.split('#').next().unwrap_or("").to_string())
+                    .unwrap_or_default(),
+                checksum,
+            });
+        }
+    }
+
+    // A package can be described once and referenced elsewhere by rdf:about,
+    // so only nodes carrying a name are treated as definitions.
+    let mut seen_packages = HashSet::new();
+    for package in doc.descendants().filter(|n| is_spdx(n, "Package") && child(n, "name").is_some()) {
+        let key = about(&package).unwrap_or_else(|| format!("{:?}", package.id()));
+        if seen_packages.insert(key) {
+            packages.packages.push(read_package(&package));
+        }
+    }
+
+    let mut relationships = Vec::new();
+    for relationship in doc.descendants().filter(|n| is_spdx(n, "Relationship")) {
+        // spdx:Relationship sits in a spdx:relationship property of its source element.
+        let Some(element) = relationship.parent().and_then(|p| p.parent()).and_then(|e| about(&e)) else {
+            continue;
+        };
+        let related = child(&relationship, "relatedSpdxElement")
+            .and_then(|r| resource(&r).or_else(|| r.children().find(|c| c.is_element()).and_then(|c| about(&c))));
+        let relationship_type = child(&relationship, "relationshipType").and_then(|t| resource(&t));
+        if let (Some(related), Some(relationship_type)) = (related, relationship_type) {
+            relationships.push(RelationshipObj{
+                spdxElementId: uri_license_id(&element),
+                relationshipType: relationship_type_name(&relationship_type),
+                relatedSpdxElement: uri_license_id(&related),
+            });
+        }
+    }
+    for described in doc.descendants().filter(|n| is_spdx(n, "describesPackage")) {
+        let package = resource(&described).or_else(|| described.children().find(|c| c.is_element()).and_then(|c| about(&c)));
+        if let Some(package) = package {
+            packages.documentDescribes.get_or_insert(Vec::new()).push(uri_license_id(&package));
+        }
+    }
+    if !relationships.is_empty() {
+        packages.relationships = Some(relationships);
+    }
+
+    let mut seen_licenses = HashSet::new();
+    let mut licenses = Vec::new();
+    for info in doc.descendants().filter(|n| is_spdx(n, "ExtractedLicensingInfo")) {
+        let Some(license_id) = child_text(&info, "licenseId") else {
+            continue;
+        };
+        if seen_licenses.insert(license_id.clone()) {
+            licenses.push(LicenseInfo{
+                licenseId: license_id,
+                extractedText: child_text(&info, "extractedText").unwrap_or_default(),
+                name: child_text(&info, "name").unwrap_or_default(),
+                comment: comment_text(&info).unwrap_or_default(),
+            });
+        }
+    }
+    if !licenses.is_empty() {
+        license_extract.hasExtractedLicensingInfos = Some(Some(licenses));
+    }
+    Ok((packages, license_extract))
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const DOCUMENT: &str = r##"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+         xmlns:rdfs="http://www.w3.org/2000/01/rdf-schema#"
+         xmlns:spdx="http://spdx.org/rdf/terms#">
+  <spdx:SpdxDocument rdf:about="https://example.com/sample#SPDXRef-DOCUMENT">
+    <spdx:name>sample</spdx:name>
+    <spdx:specVersion>SPDX-2.2</spdx:specVersion>
+    <spdx:describesPackage>
+      <spdx:Package rdf:about="https://example.com/sample#SPDXRef-app">
+        <spdx:name>app</spdx:name>
+        <spdx:versionInfo>2.0</spdx:versionInfo>
+        <spdx:licenseDeclared>
+          <spdx:ConjunctiveLicenseSet>
+            <spdx:member rdf:resource="http://spdx.org/licenses/Apache-2.0"/>
+            <spdx:member>
+              <spdx:DisjunctiveLicenseSet>
+                <spdx:member rdf:resource="http://spdx.org/licenses/MIT"/>
+                <spdx:member>
+                  <spdx:ExtractedLicensingInfo rdf:about="https://example.com/sample#LicenseRef-custom">
+                    <spdx:licenseId>LicenseRef-custom</spdx:licenseId>
+                    <spdx:name>Custom License</spdx:name>
+                    <spdx:extractedText>Custom terms</spdx:extractedText>
+                    <rdfs:comment>Found in vendor/</rdfs:comment>
+                  </spdx:ExtractedLicensingInfo>
+                </spdx:member>
+              </spdx:DisjunctiveLicenseSet>
+            </spdx:member>
+          </spdx:ConjunctiveLicenseSet>
+        </spdx:licenseDeclared>
+        <spdx:licenseConcluded rdf:resource="http://spdx.org/rdf/terms#noassertion"/>
+        <spdx:externalRef>
+          <spdx:ExternalRef>
+            <spdx:referenceCategory rdf:resource="http://spdx.org/rdf/terms#referenceCategory_packageManager"/>
+            <spdx:referenceType rdf:resource="http://spdx.org/rdf/references/purl"/>
+            <spdx:referenceLocator>pkg:maven/org.example/app@2.0</spdx:referenceLocator>
+          </spdx:ExternalRef>
+        </spdx:externalRef>
+        <spdx:relationship>
+          <spdx:Relationship>
+            <spdx:relationshipType rdf:resource="http://spdx.org/rdf/terms#relationshipType_dependsOn"/>
+            <spdx:relatedSpdxElement rdf:resource="https://example.com/sample#SPDXRef-lib"/>
+          </spdx:Relationship>
+        </spdx:relationship>
+      </spdx:Package>
+    </spdx:describesPackage>
+  </spdx:SpdxDocument>
+  <spdx:Package rdf:about="https://example.com/sample#SPDXRef-lib">
+    <spdx:name>lib</spdx:name>
+    <spdx:licenseDeclared>
+      <spdx:WithExceptionOperator>
+        <spdx:member rdf:resource="http://spdx.org/licenses/GPL-2.0-only"/>
+        <spdx:licenseException>
+          <spdx:LicenseException>
+            <spdx:licenseExceptionId>Classpath-exception-2.0</spdx:licenseExceptionId>
+          </spdx:LicenseException>
+        </spdx:licenseException>
+      </spdx:WithExceptionOperator>
+    </spdx:licenseDeclared>
+  </spdx:Package>
+  <spdx:Package rdf:about="https://example.com/sample#SPDXRef-lib"/>
+</rdf:RDF>
+"##;
+
+    #[test]
+    fn reads_nested_license_sets(){
+        let (packages, _) = parse_rdf(DOCUMENT).unwrap();
+        let app = &packages.packages[0];
+        assert_eq!(app.name, "app");
+        assert_eq!(app.SPDXID.as_deref(), Some("SPDXRef-app"));
+        assert_eq!(app.versionInfo.as_deref(), Some("2.0"));
+        assert_eq!(app.licenseDeclared.as_deref(), Some("Apache-2.0 AND (MIT OR LicenseRef-custom)"));
+        assert_eq!(app.licenseConcluded.as_deref(), Some("NOASSERTION"));
+        let lib = &packages.packages[1];
+        assert_eq!(lib.licenseDeclared.as_deref(), Some("GPL-2.0-only WITH Classpath-exception-2.0"));
+    }
+
+    #[test]
+    fn skips_package_references_without_a_name(){
+        let (packages, _) = parse_rdf(DOCUMENT).unwrap();
+        assert_eq!(packages.packages.len(), 2);
+    }
+
+    #[test]
+    fn reads_external_refs_and_relationships(){
+        let (packages, license_extract) = parse_rdf(DOCUMENT).unwrap();
+        assert_eq!(license_extract.name, "sample");
+        assert_eq!(license_extract.documentNamespace, "https://example.com/sample");
+        let reference = &packages.packages[0].externalRefs.as_ref().unwrap().as_ref().unwrap()[0];
+        assert_eq!(reference.referenceCategory, "PACKAGE-MANAGER");
+        assert_eq!(reference.referenceType, "purl");
+        assert_eq!(reference.referenceLocator, "pkg:maven/org.example/app@2.0");
+
+        assert_eq!(packages.documentDescribes, Some(vec!["SPDXRef-app".to_string()]));
+        let relationship = &packages.relationships.unwrap()[0];
+        assert_eq!(relationship.spdxElementId, "SPDXRef-app");
+        assert_eq!(relationship.relationshipType, "DEPENDS_ON");
+        assert_eq!(relationship.relatedSpdxElement, "SPDXRef-lib");
+    }
+
+    #[test]
+    fn reads_checksum_algorithms_with_underscores(){
+        let (packages, _) = parse_rdf(r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:spdx="http://spdx.org/rdf/terms#">
+  <spdx:Package rdf:about="https://example.com/sample#SPDXRef-pkg">
+    <spdx:name>pkg</spdx:name>
+    <spdx:checksum>
+      <spdx:Checksum>
+        <spdx:algorithm rdf:resource="http://spdx.org/rdf/terms#checksumAlgorithm_sha3_256"/>
+        <spdx:checksumValue>abcd</spdx:checksumValue>
+      </spdx:Checksum>
+    </spdx:checksum>
+    <spdx:checksum>
+      <spdx:Checksum>
+        <spdx:algorithm rdf:resource="http://spdx.org/rdf/terms#checksumAlgorithm_sha1"/>
+        <spdx:checksumValue>ef01</spdx:checksumValue>
+      </spdx:Checksum>
+    </spdx:checksum>
+  </spdx:Package>
+</rdf:RDF>"#).unwrap();
+        let checksums = packages.packages[0].checksums.as_ref().unwrap();
+        assert_eq!(checksums[0].algorithm, "SHA3-256");
+        assert_eq!(checksums[0].checksumValue, "abcd");
+        assert_eq!(checksums[1].algorithm, "SHA1");
+    }
+
+    #[test]
+    fn reads_inline_extracted_licensing_info(){
+        let (_, license_extract) = parse_rdf(DOCUMENT).unwrap();
+        let licenses = license_extract.hasExtractedLicensingInfos.unwrap().unwrap();
+        assert_eq!(licenses.len(), 1);
+        assert_eq!(licenses[0].licenseId, "LicenseRef-custom");
+        assert_eq!(licenses[0].name, "Custom License");
+        assert_eq!(licenses[0].extractedText, "Custom terms");
+        assert_eq!(licenses[0].comment, "Found in vendor/");
+    }
+}
@@ -0,0 +1,330 @@
+use crate::spdx_license::{ChecksumObj, CreationInfo, ExternalDocumentRef, FileObj, HasLicenseInfo, LicenseInfo, PackageObj, Packages, PointerObj, RangeObj, ReferenceObj, RelationshipObj, SnippetObj};
+
+// Which element the following tags belong to. Tags such as `LicenseComment`
+// are valid in several sections, so they are routed by the current section.
+enum Section{
+    Document,
+    Package,
+    File,
+    Snippet,
+    ExtractedLicense,
+}
+
+/// Sniffs tag-value by the `.spdx` extension or a leading `SPDXVersion:` tag.
+pub fn is_tag_value(filepath: &str, content: &str) -> bool{
+    if filepath.ends_with(".spdx") {
+        return true;
+    }
+    content.trim_start().starts_with("SPDXVersion:")
+}
+
+fn read_checksum(value: &str) -> ChecksumObj{
+    let (algorithm, checksum) = value.split_once(':').unwrap_or(("", value));
+    ChecksumObj{
+        algorithm: algorithm.trim().to_string(),
+        checksumValue: checksum.trim().to_string(),
+    }
+}
+
+/// Reads a `start:end` snippet range into byte offsets or line numbers.
+fn read_range(value: &str, lines: bool) -> RangeObj{
+    let (start, end) = value.split_once(':').unwrap_or((value, value));
+    let pointer = |position: &str| {
+        let position = position.trim().parse().ok();
+        if lines {
+            PointerObj{ lineNumber: position, ..Default::default() }
+        } else {
+            PointerObj{ offset: position, ..Default::default() }
+        }
+    };
+    RangeObj{
+        startPointer: pointer(start),
+        endPointer: pointer(end),
+    }
+}
+
+/// Splits the document into `(tag, value)` pairs, joining multi-line
+/// `<text>...</text>` values and stripping the markers.
+fn read_tag_values(content: &str) -> Vec<(String, String)>{
+    let mut pairs = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next(){
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((tag, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let mut value = value.trim().to_string();
+        if let Some(text) = value.strip_prefix("<text>") {
+            let mut text = text.to_string();
+            while !text.contains("</text>") {
+                match lines.next() {
+                    Some(next) => {
+                        text.push('\n');
+                        text.push_str(next);
+                    }
+                    None => break,
+                }
+            }
+            value = match text.find("</text>") {
+                Some(end) => text[..end].to_string(),
+                None => text,
+            };
+        }
+        pairs.push((tag.trim().to_string(), value));
+    }
+    pairs
+}
+
+/// Parses an SPDX 2.x tag-value document. `PackageName`, `FileName`,
+/// `SnippetSPDXID` and `LicenseID` each open a new element that the
+/// following tags belong to.
+pub fn parse_tag_value(content: &str) -> (Packages, HasLicenseInfo){
+    let mut packages = Packages::default();
+    let mut license_extract = HasLicenseInfo::default();
+    let mut licenses: Vec<LicenseInfo> = Vec::new();
+    let mut files: Vec<FileObj> = Vec::new();
+    let mut snippets: Vec<SnippetObj> = Vec::new();
+    let mut relationships: Vec<RelationshipObj> = Vec::new();
+    let mut section = Section::Document;
+
+    for (tag, value) in read_tag_values(content){
+        match tag.as_str() {
+            "PackageName" => {
+                packages.packages.push(PackageObj{
+                    name: value,
+                    ..Default::default()
+                });
+                section = Section::Package;
+                continue;
+            }
+            "LicenseID" => {
+                licenses.push(LicenseInfo{
+                    licenseId: value,
+                    ..Default::default()
+                });
+                section = Section::ExtractedLicense;
+                continue;
+            }
+            "FileName" => {
+                files.push(FileObj{
+                    fileName: value,
+                    ..Default::default()
+                });
+                section = Section::File;
+                continue;
+            }
+            "SnippetSPDXID" => {
+                snippets.push(SnippetObj{
+                    SPDXID: value,
+                    ..Default::default()
+                });
+                section = Section::Snippet;
+                continue;
+            }
+            "Relationship" => {
+                let mut parts = value.split_whitespace();
+                if let (Some(element), Some(relationship_type), Some(related)) = (parts.next(), parts.next(), parts.next()) {
+                    relationships.push(RelationshipObj{
+                        spdxElementId: element.to_string(),
+                        relationshipType: relationship_type.to_string(),
+                        relatedSpdxElement: related.to_string(),
+                    });
+                }
+                continue;
+            }
+            _ => {}
+        }
+        match section {
+            Section::Document => match tag.as_str() {
+                "DocumentName" => license_extract.name = value,
+                "DocumentNamespace" => license_extract.documentNamespace = value,
+                "SPDXVersion" => license_extract.spdxVersion = Some(value),
+                "DataLicense" => license_extract.dataLicense = Some(value),
+                "Creator" => license_extract.creationInfo.get_or_insert_with(CreationInfo::default)
+                    .creators.get_or_insert(Vec::new())
+                    .push(value),
+                "Created" => license_extract.creationInfo.get_or_insert_with(CreationInfo::default).created = Some(value),
+                "LicenseListVersion" => license_extract.creationInfo.get_or_insert_with(CreationInfo::default).licenseListVersion = Some(value),
+                "ExternalDocumentRef" => {
+                    let mut parts = value.splitn(3, char::is_whitespace);
+                    let reference = ExternalDocumentRef{
+                        externalDocumentId: parts.next().unwrap_or("").to_string(),
+                        spdxDocument: parts.next().unwrap_or("").to_string(),
+                        checksum: parts.next().map(read_checksum),
+                    };
+                    license_extract.externalDocumentRefs.get_or_insert(Vec::new()).push(reference);
+                }
+                _ => {}
+            },
+            Section::Package => {
+                let Some(package) = packages.packages.last_mut() else {
+                    continue;
+                };
+                match tag.as_str() {
+                    "SPDXID" => package.SPDXID = Some(value),
+                    "PackageVersion" => package.versionInfo = Some(value),
+                    "PackageLicenseInfoFromFiles" => package.licenseInfoFromFiles.get_or_insert(Vec::new()).push(value),
+                    "PackageLicenseDeclared" => package.licenseDeclared = Some(value),
+                    "PackageSupplier" => package.supplier = Some(value),
+                    "PackageOriginator" => package.originator = Some(value),
+                    "PackageCopyrightText" => package.copyrightText = Some(value),
+                    "PackageDownloadLocation" => package.downloadLocation = Some(value),
+                    "PackageHomePage" => package.homepage = Some(value),
+                    "PackageChecksum" => package.checksums.get_or_insert(Vec::new()).push(read_checksum(&value)),
+                    "PackageLicenseConcluded" => package.licenseConcluded = Some(value),
+                    "ExternalRef" => {
+                        let mut parts = value.splitn(3, char::is_whitespace);
+                        let reference = ReferenceObj{
+                            referenceCategory: parts.next().unwrap_or("").to_string(),
+                            referenceType: parts.next().unwrap_or("").to_string(),
+                            referenceLocator: parts.next().unwrap_or("").trim().to_string(),
+                        };
+                        package.externalRefs.get_or_insert(Some(Vec::new()))
+                            .get_or_insert(Vec::new())
+                            .push(reference);
+                    }
+                    _ => {}
+                }
+            }
+            Section::File => {
+                let Some(file) = files.last_mut() else {
+                    continue;
+                };
+                match tag.as_str() {
+                    "SPDXID" => {
+                        // Files listed after a package belong to that package.
+                        if let Some(package) = packages.packages.last_mut() {
+                            package.hasFiles.get_or_insert(Vec::new()).push(value.clone());
+                        }
+                        file.SPDXID = value;
+                    }
+                    "FileChecksum" => file.checksums.get_or_insert(Vec::new()).push(read_checksum(&value)),
+                    "LicenseConcluded" => file.licenseConcluded = Some(value),
+                    "LicenseInfoInFile" => file.licenseInfoInFiles.get_or_insert(Vec::new()).push(value),
+                    _ => {}
+                }
+            }
+            Section::Snippet => {
+                let Some(snippet) = snippets.last_mut() else {
+                    continue;
+                };
+                match tag.as_str() {
+                    "SnippetFromFileSPDXID" => snippet.snippetFromFile = value,
+                    "SnippetName" => snippet.name = Some(value),
+                    "SnippetByteRange" => snippet.ranges.get_or_insert(Vec::new()).push(read_range(&value, false)),
+                    "SnippetLineRange" => snippet.ranges.get_or_insert(Vec::new()).push(read_range(&value, true)),
+                    "SnippetLicenseConcluded" => snippet.licenseConcluded = Some(value),
+                    "LicenseInfoInSnippet" => snippet.licenseInfoInSnippets.get_or_insert(Vec::new()).push(value),
+                    _ => {}
+                }
+            }
+            Section::ExtractedLicense => {
+                let Some(license) = licenses.last_mut() else {
+                    continue;
+                };
+                match tag.as_str() {
+                    "ExtractedText" => license.extractedText = value,
+                    "LicenseName" => license.name = value,
+                    "LicenseComment" => license.comment = value,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    if !licenses.is_empty() {
+        license_extract.hasExtractedLicensingInfos = Some(Some(licenses));
+    }
+    if !files.is_empty() {
+        packages.files = Some(files);
+    }
+    if !snippets.is_empty() {
+        packages.snippets = Some(snippets);
+    }
+    if !relationships.is_empty() {
+        packages.relationships = Some(relationships);
+    }
+    (packages, license_extract)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const DOCUMENT: &str = "SPDXVersion: SPDX-2.3
+DataLicense: CC0-1.0
+DocumentName: sample
+DocumentNamespace: https://example.com/sample
+Creator: Tool: sbom-tool
+Created: 2024-01-01T00:00:00Z
+
+PackageName: left-pad
+SPDXID: SPDXRef-left-pad
+PackageVersion: 1.3.0
+PackageLicenseDeclared: MIT
+PackageLicenseConcluded: (MIT OR LicenseRef-custom)
+ExternalRef: PACKAGE-MANAGER purl pkg:npm/left-pad@1.3.0
+# comment lines are skipped
+Relationship: SPDXRef-DOCUMENT DESCRIBES SPDXRef-left-pad
+
+LicenseID: LicenseRef-custom
+ExtractedText: <text>Line one
+Line two: with a colon
+</text>
+LicenseName: Custom License
+LicenseComment: <text>Single-line comment</text>
+";
+
+    #[test]
+    fn reads_document_and_package_fields(){
+        let (packages, license_extract) = parse_tag_value(DOCUMENT);
+        assert_eq!(license_extract.name, "sample");
+        assert_eq!(license_extract.documentNamespace, "https://example.com/sample");
+        assert_eq!(license_extract.spdxVersion.as_deref(), Some("SPDX-2.3"));
+        let creation_info = license_extract.creationInfo.unwrap();
+        assert_eq!(creation_info.creators, Some(vec!["Tool: sbom-tool".to_string()]));
+
+        assert_eq!(packages.packages.len(), 1);
+        let package = &packages.packages[0];
+        assert_eq!(package.name, "left-pad");
+        assert_eq!(package.SPDXID.as_deref(), Some("SPDXRef-left-pad"));
+        assert_eq!(package.versionInfo.as_deref(), Some("1.3.0"));
+        assert_eq!(package.licenseDeclared.as_deref(), Some("MIT"));
+        assert_eq!(package.licenseConcluded.as_deref(), Some("(MIT OR LicenseRef-custom)"));
+        let reference = &package.externalRefs.as_ref().unwrap().as_ref().unwrap()[0];
+        assert_eq!(reference.referenceType, "purl");
+        assert_eq!(reference.referenceLocator, "pkg:npm/left-pad@1.3.0");
+
+        let relationship = &packages.relationships.unwrap()[0];
+        assert_eq!(relationship.relationshipType, "DESCRIBES");
+        assert_eq!(relationship.relatedSpdxElement, "SPDXRef-left-pad");
+    }
+
+    #[test]
+    fn joins_multi_line_text_blocks(){
+        let (_, license_extract) = parse_tag_value(DOCUMENT);
+        let licenses = license_extract.hasExtractedLicensingInfos.unwrap().unwrap();
+        assert_eq!(licenses.len(), 1);
+        assert_eq!(licenses[0].licenseId, "LicenseRef-custom");
+        assert_eq!(licenses[0].extractedText, "Line one\nLine two: with a colon\n");
+        assert_eq!(licenses[0].name, "Custom License");
+        assert_eq!(licenses[0].comment, "Single-line comment");
+    }
+
+    #[test]
+    fn keeps_unterminated_text_block(){
+        let (_, license_extract) = parse_tag_value("LicenseID: LicenseRef-x\nExtractedText: <text>no end\nstill text");
+        let licenses = license_extract.hasExtractedLicensingInfos.unwrap().unwrap();
+        assert_eq!(licenses[0].extractedText, "no end\nstill text");
+    }
+
+    #[test]
+    fn sniffs_tag_value(){
+        assert!(is_tag_value("sbom.spdx", ""));
+        assert!(is_tag_value("sbom.txt", "\nSPDXVersion: SPDX-2.3"));
+        assert!(!is_tag_value("sbom.json", "{\"spdxVersion\": \"SPDX-2.3\"}"));
+    }
+}